@@ -0,0 +1,24 @@
+//! `cargo +nightly fuzz run parse_query`: arbitrary queries and working
+//! directories must parse without panicking and keep the search base on the
+//! path from cwd to the root.
+
+#![no_main]
+
+use claude_search::parse_query;
+use libfuzzer_sys::fuzz_target;
+use std::path::Path;
+
+fuzz_target!(|input: (&str, &str)| {
+    let (query, cwd) = input;
+    let cwd = Path::new(cwd);
+    let parsed = parse_query(query, cwd);
+
+    let ascents = parsed.output_prefix.len() / 3;
+    assert_eq!(parsed.output_prefix, "../".repeat(ascents));
+    assert_eq!(
+        cwd.ancestors().nth(ascents),
+        Some(parsed.search_base.as_path())
+    );
+    assert!(!parsed.pattern.starts_with("./") && !parsed.pattern.starts_with("../"));
+    assert_eq!(parsed.is_empty, parsed.pattern.is_empty());
+});
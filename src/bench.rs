@@ -0,0 +1,368 @@
+//! `bench`: time the walk and match phases on a reproducible synthetic tree.
+
+use claude_search::Search;
+use serde_json::json;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Directory and file name parts for generated trees
+const DIR_NAMES: &[&str] = &[
+    "src",
+    "lib",
+    "components",
+    "utils",
+    "api",
+    "core",
+    "models",
+    "services",
+    "handlers",
+    "config",
+    "tests",
+    "docs",
+];
+const FILE_NAMES: &[&str] = &[
+    "main", "index", "button", "config", "helpers", "router", "user", "session", "parser",
+    "client", "server", "types",
+];
+const EXTENSIONS: &[&str] = &["rs", "ts", "tsx", "py", "go", "md", "json"];
+
+/// Unmeasured runs before timing starts
+const WARMUP_RUNS: usize = 3;
+
+/// Shape of the generated tree and how to measure it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Regular source files
+    pub files: usize,
+    /// Maximum directory nesting for source files
+    pub depth: usize,
+    /// Files hidden by .gitignore rules
+    pub ignored: usize,
+    /// Files inside node_modules
+    pub bulk: usize,
+    pub runs: usize,
+    pub seed: u64,
+    /// Queries to time (the empty query is the shallow listing)
+    pub queries: Vec<String>,
+    /// Print one JSON object instead of a table
+    pub json: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            files: 1000,
+            depth: 4,
+            ignored: 200,
+            bulk: 2000,
+            runs: 50,
+            seed: 42,
+            queries: vec!["main".to_string(), "src/api".to_string(), String::new()],
+            json: false,
+        }
+    }
+}
+
+/// Generate the tree in a temporary directory, time every query and print
+/// percentiles per phase.
+pub fn run(options: &Options) -> Result<(), Box<dyn Error>> {
+    let root = std::env::temp_dir().join(format!("claude-search-bench-{}", std::process::id()));
+    let _cleanup = RemoveOnDrop(root.clone());
+    generate(&root, options)
+        .map_err(|err| format!("cannot create the tree in {}: {err}", root.display()))?;
+
+    let reports = options
+        .queries
+        .iter()
+        .map(|query| measure(&root, query, options.runs))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut out = io::stdout().lock();
+    if options.json {
+        let queries: Vec<_> = reports.iter().map(QueryReport::to_json).collect();
+        let report = json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "files": options.files,
+            "depth": options.depth,
+            "ignored": options.ignored,
+            "bulk": options.bulk,
+            "runs": options.runs,
+            "seed": options.seed,
+            "queries": queries,
+        });
+        writeln!(out, "{report}")?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "claude-search {} | {} files, depth {}, {} ignored, {} in node_modules, seed {}, {} runs",
+        env!("CARGO_PKG_VERSION"),
+        options.files,
+        options.depth,
+        options.ignored,
+        options.bulk,
+        options.seed,
+        options.runs
+    )?;
+    for report in &reports {
+        writeln!(
+            out,
+            "\nquery {:?}: {} candidates, {} results",
+            report.query, report.candidates, report.results
+        )?;
+        writeln!(out, "  phase        p50        p90        p99        max")?;
+        for (name, samples) in report.phases() {
+            write!(out, "  {name:<6}")?;
+            for p in [50.0, 90.0, 99.0, 100.0] {
+                write!(out, " {:>10}", format_duration(percentile(samples, p)))?;
+            }
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Timings of one query over all runs.
+struct QueryReport {
+    query: String,
+    candidates: usize,
+    results: usize,
+    walk: Vec<Duration>,
+    matching: Vec<Duration>,
+    total: Vec<Duration>,
+}
+
+impl QueryReport {
+    fn phases(&self) -> [(&'static str, &[Duration]); 3] {
+        [
+            ("walk", &self.walk),
+            ("match", &self.matching),
+            ("total", &self.total),
+        ]
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut phases = serde_json::Map::new();
+        for (name, samples) in self.phases() {
+            let ms = |p| (percentile(samples, p).as_secs_f64() * 1e6).round() / 1000.0;
+            phases.insert(
+                name.to_string(),
+                json!({ "p50_ms": ms(50.0), "p90_ms": ms(90.0), "p99_ms": ms(99.0), "max_ms": ms(100.0) }),
+            );
+        }
+        json!({
+            "query": self.query,
+            "candidates": self.candidates,
+            "results": self.results,
+            "phases": phases,
+        })
+    }
+}
+
+/// Time `runs` searches; a walk that fails or cannot read part of the tree
+/// would time the wrong thing, so it is an error.
+fn measure(root: &Path, query: &str, runs: usize) -> Result<QueryReport, Box<dyn Error>> {
+    let search = Search::new(root).query(query);
+    for _ in 0..WARMUP_RUNS {
+        search.run();
+    }
+
+    let mut report = QueryReport {
+        query: query.to_string(),
+        candidates: 0,
+        results: 0,
+        walk: Vec::with_capacity(runs),
+        matching: Vec::with_capacity(runs),
+        total: Vec::with_capacity(runs),
+    };
+    for _ in 0..runs {
+        let (results, stats) = search.run_with_stats();
+        if stats.search_base.is_none() {
+            return Err(format!(
+                "query {query:?}: no directory to search in {}",
+                root.display()
+            )
+            .into());
+        }
+        if stats.walk_errors > 0 {
+            return Err(format!(
+                "query {query:?}: {} entries under {} could not be read",
+                stats.walk_errors,
+                root.display()
+            )
+            .into());
+        }
+        report.candidates = stats.candidates;
+        report.results = results.len();
+        report.walk.push(stats.walk_time);
+        report.matching.push(stats.match_time);
+        report.total.push(stats.walk_time + stats.match_time);
+    }
+    for samples in [&mut report.walk, &mut report.matching, &mut report.total] {
+        samples.sort_unstable();
+    }
+    Ok(report)
+}
+
+/// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Write the synthetic tree described by `options` under `root`. The same
+/// options always produce the same paths.
+pub fn generate(root: &Path, options: &Options) -> io::Result<Vec<PathBuf>> {
+    let mut rng = Rng(options.seed);
+    let mut paths = Vec::new();
+
+    // A repository, so .gitignore rules apply
+    fs::create_dir_all(root.join(".git"))?;
+    fs::write(root.join(".gitignore"), "*.log\ngenerated/\n")?;
+
+    for _ in 0..options.files {
+        let depth = rng.below(options.depth + 1);
+        let mut path = PathBuf::new();
+        for _ in 0..depth {
+            path.push(rng.pick(DIR_NAMES));
+        }
+        path.push(file_name(&mut rng, paths.len()));
+        paths.push(path);
+    }
+    for i in 0..options.ignored {
+        let name = format!("{}-{i}.log", rng.pick(FILE_NAMES));
+        // Half in an ignored directory, half matched by a file pattern
+        paths.push(if i % 2 == 0 {
+            Path::new("generated").join(name)
+        } else {
+            PathBuf::from(name)
+        });
+    }
+    for i in 0..options.bulk {
+        let package = format!("pkg-{}", rng.below(options.bulk / 20 + 1));
+        paths.push(
+            Path::new("node_modules")
+                .join(package)
+                .join(rng.pick(DIR_NAMES))
+                .join(format!("{}{i}.js", rng.pick(FILE_NAMES))),
+        );
+    }
+
+    for path in &paths {
+        let path = root.join(path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, "")?;
+    }
+    Ok(paths)
+}
+
+/// A unique file name; `n` keeps names distinct within a directory.
+fn file_name(rng: &mut Rng, n: usize) -> String {
+    format!("{}_{n}.{}", rng.pick(FILE_NAMES), rng.pick(EXTENSIONS))
+}
+
+/// splitmix64, so trees are reproducible without extra dependencies.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n.max(1) as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn small() -> Options {
+        Options {
+            files: 50,
+            depth: 3,
+            ignored: 10,
+            bulk: 20,
+            runs: 3,
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_is_reproducible() {
+        let (a, b) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+        let paths = generate(a.path(), &small()).unwrap();
+        assert_eq!(paths, generate(b.path(), &small()).unwrap());
+        assert_eq!(paths.len(), 80);
+        assert!(paths.iter().all(|p| a.path().join(p).is_file()));
+
+        let other = Options { seed: 7, ..small() };
+        let c = TempDir::new().unwrap();
+        assert_ne!(paths, generate(c.path(), &other).unwrap());
+    }
+
+    #[test]
+    fn test_generated_ignore_rules_apply() {
+        let dir = TempDir::new().unwrap();
+        generate(dir.path(), &small()).unwrap();
+
+        let report = measure(dir.path(), "", 1).unwrap();
+        assert!(report.candidates > 0);
+        // Ignored and bulk files are generated but never suggested
+        let all = Search::new(dir.path()).query("log").limit(usize::MAX).run();
+        assert!(!all.is_empty());
+        assert!(!all.iter().any(|r| r.path.ends_with(".log")
+            || r.path.starts_with("generated")
+            || r.path.starts_with("node_modules")));
+    }
+
+    #[test]
+    fn test_measure_fails_without_tree() {
+        let dir = TempDir::new().unwrap();
+        let Err(err) = measure(&dir.path().join("missing"), "main", 1) else {
+            panic!("measured a missing tree");
+        };
+        assert!(err.to_string().contains("no directory to search"), "{err}");
+    }
+
+    #[test]
+    fn test_percentile() {
+        let samples: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&samples, 50.0), Duration::from_millis(50));
+        assert_eq!(percentile(&samples, 99.0), Duration::from_millis(99));
+        assert_eq!(percentile(&samples, 100.0), Duration::from_millis(100));
+        assert_eq!(percentile(&samples[..1], 90.0), Duration::from_millis(1));
+        assert_eq!(percentile(&[], 50.0), Duration::ZERO);
+    }
+}
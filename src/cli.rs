@@ -0,0 +1,364 @@
+//! Command-line argument parsing.
+
+use crate::bench;
+use crate::install::Scope;
+use claude_search::Input;
+use serde::de::DeserializeOwned;
+use std::ffi::OsString;
+
+pub const USAGE: &str = "\
+Usage:
+  claude-search                        Read a JSON request from stdin (Claude Code)
+  claude-search find [QUERY] [OPTIONS]  Search from the command line
+  claude-search doctor <PATH> [OPTIONS] Explain why a path is or is not suggested
+  claude-search mcp                    Run an MCP server over stdio
+  claude-search install [--user|--project]
+                                       Add claude-search to Claude Code's settings
+  claude-search uninstall [--user|--project]
+                                       Remove it again, restoring any previous command
+  claude-search bench [OPTIONS]        Time searches on a generated tree
+
+Options for find and doctor:
+      --query <QUERY>          Query to test (doctor defaults to the file name)
+      --cwd <DIR>              Directory to search from [default: current directory]
+      --limit <N>              Maximum number of results [default: 50]
+      --format <FORMAT>        lines, json, nul or explain [default: lines]
+      --include-ignored        Also search gitignored files and skipped directories
+      --symlinks <POLICY>      never, within_root or anywhere
+      --submodules <POLICY>    include or exclude
+      --search-root <ROOT>     cwd or project
+      --sensitive <POLICY>     hide, flag or show secret-like files [default: hide]
+      --root <DIR>             Also search DIR, repeatable (scope with root:NAME)
+
+Options for bench:
+      --files <N>              Source files [default: 1000]
+      --depth <N>              Maximum directory depth of source files [default: 4]
+      --ignored <N>            Files hidden by .gitignore [default: 200]
+      --bulk <N>               Files in node_modules [default: 2000]
+      --runs <N>               Timed runs per query [default: 50]
+      --seed <N>               Seed for the generated tree [default: 42]
+      --query <QUERY>          Query to time, repeatable [default: main, src/api, empty]
+      --json                   Print a single JSON report
+
+  -h, --help                   Print help
+  -V, --version                Print version
+";
+
+/// What the binary was asked to do.
+#[derive(Debug)]
+pub enum Command {
+    /// No arguments: a JSON request on stdin, as sent by Claude Code
+    Hook,
+    Mcp,
+    Find(Input),
+    /// Explain the ranking of one path for the query in `Input`
+    Doctor(Input, String),
+    Install(Scope),
+    Uninstall(Scope),
+    Bench(bench::Options),
+    Help,
+    Version,
+}
+
+/// Parse the arguments after the program name.
+pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Command, String> {
+    let mut args = args
+        .into_iter()
+        .map(|arg| {
+            arg.into_string()
+                .map_err(|arg| format!("invalid UTF-8 in argument {arg:?}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+
+    let Some(command) = args.next() else {
+        return Ok(Command::Hook);
+    };
+    match command.as_str() {
+        "find" => {
+            let (mut input, words) = parse_search(args)?;
+            // Unquoted words form one query, e.g. `find sub:libfoo main`
+            if !words.is_empty() {
+                input.query = Some(words.join(" "));
+            }
+            Ok(Command::Find(input))
+        }
+        "doctor" => {
+            let (input, mut words) = parse_search(args)?;
+            match (words.pop(), words.is_empty()) {
+                (Some(path), true) => Ok(Command::Doctor(input, path)),
+                _ => Err("doctor takes exactly one path".to_string()),
+            }
+        }
+        "mcp" => Ok(Command::Mcp),
+        "install" => parse_scope(args).map(Command::Install),
+        "uninstall" => parse_scope(args).map(Command::Uninstall),
+        "bench" => parse_bench(args).map(Command::Bench),
+        "-h" | "--help" | "help" => Ok(Command::Help),
+        "-V" | "--version" => Ok(Command::Version),
+        _ => Err(format!("unknown command '{command}'")),
+    }
+}
+
+/// Parse search options, returning the remaining positional words.
+fn parse_search(mut args: impl Iterator<Item = String>) -> Result<(Input, Vec<String>), String> {
+    let mut input = Input::default();
+    let mut words = Vec::new();
+    let mut options_done = false;
+
+    while let Some(arg) = args.next() {
+        if options_done || !arg.starts_with("--") {
+            words.push(arg);
+            continue;
+        }
+        if arg == "--" {
+            options_done = true;
+            continue;
+        }
+        // Accept both "--opt value" and "--opt=value"
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        if name == "--include-ignored" {
+            if inline.is_some() {
+                return Err(format!("{name} takes no value"));
+            }
+            input.include_ignored = true;
+            continue;
+        }
+        let value = match inline {
+            Some(value) => value,
+            None => args
+                .next()
+                .ok_or_else(|| format!("{name} requires a value"))?,
+        };
+        match name.as_str() {
+            "--query" => input.query = Some(value),
+            "--cwd" => input.cwd = Some(value),
+            "--limit" => {
+                let limit = value
+                    .parse()
+                    .map_err(|_| format!("invalid value '{value}' for --limit"))?;
+                input.limit = Some(limit);
+            }
+            "--format" => input.format = parse_value(&name, value)?,
+            "--symlinks" => input.symlinks = Some(parse_value(&name, value)?),
+            "--submodules" => input.submodules = Some(parse_value(&name, value)?),
+            "--search-root" => input.search_root = Some(parse_value(&name, value)?),
+            "--sensitive" => input.sensitive = Some(parse_value(&name, value)?),
+            "--root" => input.additional_roots.push(value),
+            _ => return Err(format!("unknown option '{name}'")),
+        }
+    }
+
+    Ok((input, words))
+}
+
+/// Settings file chosen by `--user` (the default) or `--project`.
+fn parse_scope(args: impl Iterator<Item = String>) -> Result<Scope, String> {
+    let mut scope = Scope::default();
+    for arg in args {
+        scope = match arg.as_str() {
+            "--user" => Scope::User,
+            "--project" => Scope::Project,
+            _ => return Err(format!("unknown option '{arg}'")),
+        };
+    }
+    Ok(scope)
+}
+
+/// Parse bench options; `--query` replaces the default queries.
+fn parse_bench(mut args: impl Iterator<Item = String>) -> Result<bench::Options, String> {
+    let mut options = bench::Options::default();
+    let mut queries = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--json" {
+            options.json = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            return Err(format!("unexpected argument '{arg}'"));
+        }
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("{arg} requires a value"))?;
+                (arg, value)
+            }
+        };
+        let number = || {
+            value
+                .parse::<usize>()
+                .map_err(|_| format!("invalid value '{value}' for {name}"))
+        };
+        match name.as_str() {
+            "--files" => options.files = number()?,
+            "--depth" => options.depth = number()?,
+            "--ignored" => options.ignored = number()?,
+            "--bulk" => options.bulk = number()?,
+            "--runs" => options.runs = number()?.max(1),
+            "--seed" => options.seed = number()? as u64,
+            "--query" => queries.push(value),
+            _ => return Err(format!("unknown option '{name}'")),
+        }
+    }
+
+    if !queries.is_empty() {
+        options.queries = queries;
+    }
+    Ok(options)
+}
+
+/// Parse an option value with the same names as the JSON input.
+fn parse_value<T: DeserializeOwned>(name: &str, value: String) -> Result<T, String> {
+    serde_json::from_value(serde_json::Value::String(value.clone()))
+        .map_err(|_| format!("invalid value '{value}' for {name}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claude_search::{OutputFormat, SearchRoot, SensitivePolicy, SymlinkPolicy};
+
+    fn parse_args(args: &[&str]) -> Result<Command, String> {
+        parse(args.iter().map(OsString::from))
+    }
+
+    fn find(args: &[&str]) -> Input {
+        match parse_args(args).unwrap() {
+            Command::Find(input) => input,
+            other => panic!("expected find, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_no_args_is_hook_mode() {
+        assert!(matches!(parse_args(&[]).unwrap(), Command::Hook));
+        assert!(matches!(parse_args(&["mcp"]).unwrap(), Command::Mcp));
+        assert!(matches!(parse_args(&["--help"]).unwrap(), Command::Help));
+        assert!(matches!(parse_args(&["-V"]).unwrap(), Command::Version));
+    }
+
+    #[test]
+    fn test_find_options() {
+        let input = find(&[
+            "find",
+            "../src/main",
+            "--cwd",
+            "/tmp/project",
+            "--limit=5",
+            "--format",
+            "json",
+            "--include-ignored",
+            "--symlinks",
+            "within_root",
+            "--search-root=project",
+            "--sensitive",
+            "flag",
+            "--root",
+            "../api",
+            "--root=/srv/shared",
+        ]);
+        assert_eq!(input.query.as_deref(), Some("../src/main"));
+        assert_eq!(input.cwd.as_deref(), Some("/tmp/project"));
+        assert_eq!(input.limit, Some(5));
+        assert_eq!(input.format, OutputFormat::Json);
+        assert!(input.include_ignored);
+        assert_eq!(input.symlinks, Some(SymlinkPolicy::WithinRoot));
+        assert_eq!(input.search_root, Some(SearchRoot::Project));
+        assert_eq!(input.submodules, None);
+        assert_eq!(input.sensitive, Some(SensitivePolicy::Flag));
+        assert_eq!(input.additional_roots, vec!["../api", "/srv/shared"]);
+    }
+
+    #[test]
+    fn test_find_query_words() {
+        assert_eq!(find(&["find"]).query, None);
+        assert_eq!(
+            find(&["find", "sub:libfoo", "main"]).query.as_deref(),
+            Some("sub:libfoo main")
+        );
+        // Everything after "--" is query text
+        assert_eq!(
+            find(&["find", "--", "--weird"]).query.as_deref(),
+            Some("--weird")
+        );
+    }
+
+    #[test]
+    fn test_doctor() {
+        match parse_args(&["doctor", "src/main.rs", "--query", "main", "--limit", "5"]).unwrap() {
+            Command::Doctor(input, path) => {
+                assert_eq!(path, "src/main.rs");
+                assert_eq!(input.query.as_deref(), Some("main"));
+                assert_eq!(input.limit, Some(5));
+            }
+            other => panic!("expected doctor, got {other:?}"),
+        }
+        assert!(parse_args(&["doctor"]).is_err());
+        assert!(parse_args(&["doctor", "a.rs", "b.rs"]).is_err());
+    }
+
+    #[test]
+    fn test_install_scope() {
+        assert!(matches!(
+            parse_args(&["install"]).unwrap(),
+            Command::Install(Scope::User)
+        ));
+        assert!(matches!(
+            parse_args(&["uninstall", "--project"]).unwrap(),
+            Command::Uninstall(Scope::Project)
+        ));
+        assert!(parse_args(&["install", "--global"]).is_err());
+    }
+
+    #[test]
+    fn test_bench_options() {
+        let Command::Bench(options) = parse_args(&["bench"]).unwrap() else {
+            panic!("expected bench");
+        };
+        assert_eq!(options, bench::Options::default());
+
+        let Command::Bench(options) = parse_args(&[
+            "bench",
+            "--files=10",
+            "--bulk",
+            "0",
+            "--runs",
+            "0",
+            "--seed",
+            "7",
+            "--query",
+            "a",
+            "--query=",
+            "--json",
+        ])
+        .unwrap() else {
+            panic!("expected bench");
+        };
+        assert_eq!(options.files, 10);
+        assert_eq!(options.bulk, 0);
+        assert_eq!(options.runs, 1);
+        assert_eq!(options.seed, 7);
+        assert_eq!(options.queries, vec!["a".to_string(), String::new()]);
+        assert!(options.json);
+
+        assert!(parse_args(&["bench", "--files", "-1"]).is_err());
+        assert!(parse_args(&["bench", "--runs"]).is_err());
+        assert!(parse_args(&["bench", "main"]).is_err());
+    }
+
+    #[test]
+    fn test_find_errors() {
+        assert!(parse_args(&["search"]).is_err());
+        assert!(parse_args(&["find", "--limit"]).is_err());
+        assert!(parse_args(&["find", "--limit", "many"]).is_err());
+        assert!(parse_args(&["find", "--format", "xml"]).is_err());
+        assert!(parse_args(&["find", "--verbose"]).is_err());
+        assert!(parse_args(&["find", "--include-ignored=false"]).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::project::SearchRoot;
+use crate::sensitive::{SensitiveFiles, SensitivePolicy};
+use crate::submodules::SubmodulePolicy;
+use crate::walker::{Limits, SymlinkPolicy};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable overriding the config file location
+const CONFIG_ENV: &str = "CLAUDE_SEARCH_CONFIG";
+
+/// User configuration, read from `~/.config/claude-search/config.json`.
+///
+/// Every field is optional. A missing or malformed file falls back to the
+/// defaults so the hook never fails because of configuration.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct Config {
+    /// Whether symlinks are followed while walking
+    pub symlinks: SymlinkPolicy,
+    /// Whether submodules and nested repositories are searched
+    pub submodules: SubmodulePolicy,
+    /// Whether queries search the whole project or just the working directory
+    pub search_root: SearchRoot,
+    /// Whether secret-like files are hidden, ranked last or shown
+    pub sensitive: SensitivePolicy,
+    /// Patterns replacing [`DEFAULT_SENSITIVE_PATTERNS`](crate::DEFAULT_SENSITIVE_PATTERNS)
+    pub sensitive_patterns: Option<Vec<String>>,
+    /// Directories searched alongside the working directory (relative ones
+    /// are resolved against it)
+    pub additional_roots: Vec<PathBuf>,
+    /// Caps for walks that reach `/` or the home directory
+    pub limits: Limits,
+    /// Write a diagnostic record per request to the log under [`state_dir`]
+    pub log: bool,
+}
+
+impl Config {
+    /// Load the user config, falling back to defaults.
+    pub fn load() -> Self {
+        config_path()
+            .and_then(|path| Self::from_file(&path))
+            .unwrap_or_default()
+    }
+
+    /// The configured sensitive-file patterns, or the built-in ones.
+    pub fn sensitive_files(&self) -> SensitiveFiles {
+        match &self.sensitive_patterns {
+            Some(patterns) => SensitiveFiles::new(patterns),
+            None => SensitiveFiles::default(),
+        }
+    }
+
+    fn from_file(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+}
+
+/// Resolve the config file: `$CLAUDE_SEARCH_CONFIG`, then
+/// `$XDG_CONFIG_HOME/claude-search/config.json`, then `~/.config/...`.
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".config")))?;
+    Some(config_dir.join("claude-search").join("config.json"))
+}
+
+/// Directory for logs: `$XDG_STATE_HOME/claude-search`, or
+/// `~/.local/state/claude-search`.
+pub fn state_dir() -> Option<PathBuf> {
+    let state_home = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".local").join("state")))?;
+    Some(state_home.join("claude-search"))
+}
+
+/// Current user's home directory (`$HOME`, or `%USERPROFILE%` on Windows).
+pub fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_from_file_reads_symlink_policy() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(
+            &path,
+            r#"{"symlinks": "within_root", "submodules": "exclude", "search_root": "project"}"#,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.symlinks, SymlinkPolicy::WithinRoot);
+        assert_eq!(config.submodules, SubmodulePolicy::Exclude);
+        assert_eq!(config.search_root, SearchRoot::Project);
+    }
+
+    #[test]
+    fn test_from_file_missing_fields_use_defaults() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, "{}").unwrap();
+
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.symlinks, SymlinkPolicy::Never);
+        assert_eq!(config.submodules, SubmodulePolicy::Include);
+        assert_eq!(config.search_root, SearchRoot::Cwd);
+        assert!(!config.log);
+        assert_eq!(config.limits, Limits::default());
+    }
+
+    #[test]
+    fn test_from_file_reads_limits() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(
+            &path,
+            r#"{"limits": {"broad_max_depth": 3, "same_file_system": false}}"#,
+        )
+        .unwrap();
+
+        let limits = Config::from_file(&path).unwrap().limits;
+        assert_eq!(limits.broad_max_depth, 3);
+        assert!(!limits.same_file_system);
+        assert_eq!(limits.max_entries, Limits::default().max_entries);
+    }
+
+    #[test]
+    fn test_from_file_invalid_returns_none() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.json");
+        fs::write(&path, "not json").unwrap();
+
+        assert!(Config::from_file(&path).is_none());
+        assert!(Config::from_file(&dir.path().join("missing.json")).is_none());
+    }
+}
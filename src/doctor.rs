@@ -0,0 +1,532 @@
+use crate::project::normalize;
+use crate::search::{self, Search};
+use crate::sensitive::SensitivePolicy;
+use crate::settings::DenyRules;
+use crate::submodules::{self, SubmodulePolicy};
+use crate::walker::{ALWAYS_SKIP_DIRS, IgnoreRules, SKIP_DIRS};
+use ignore::gitignore::Glob;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Why a path is or is not suggested, as reported by [`Search::diagnose`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnosis {
+    /// The path as it would be suggested (relative to the working directory)
+    pub path: String,
+    /// 1-based rank among all matches, if the path matches the query at all
+    pub rank: Option<usize>,
+    pub score: Option<u32>,
+    /// Everything keeping the path out of the results (empty if it is shown)
+    pub reasons: Vec<Reason>,
+}
+
+impl Diagnosis {
+    /// Whether the path appears in the results.
+    pub fn is_suggested(&self) -> bool {
+        self.reasons.is_empty()
+    }
+}
+
+/// One reason a path is missing from the results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Reason {
+    /// The path does not exist
+    Missing,
+    /// The query searches a directory that does not exist (e.g. unknown `sub:`)
+    NoSearchBase,
+    /// The path is not below the directory the query searches
+    OutsideSearchBase { base: PathBuf },
+    /// The path is inside a directory from `SKIP_DIRS`
+    SkipDir { dir: String, always: bool },
+    /// Claude Code's settings deny reading the path (or a parent directory)
+    Denied {
+        /// The settings file holding the rule
+        file: PathBuf,
+        rule: String,
+    },
+    /// The path matches a sensitive-file pattern and those are hidden
+    Sensitive { pattern: String },
+    /// The path (or a parent directory) matches an ignore rule
+    Ignored {
+        /// The ignore file, `None` for rules without a source
+        file: Option<PathBuf>,
+        /// 1-based line of the rule in `file`
+        line: Option<usize>,
+        pattern: String,
+    },
+    /// The path is inside a nested repository and submodules are excluded
+    NestedRepo { dir: String },
+    /// The path is deeper than the walk goes
+    TooDeep { depth: usize, max_depth: usize },
+    /// The query does not fuzzy-match the path
+    NoMatch,
+    /// A broad walk stopped at `Limits::max_entries`, possibly before the path
+    WalkStopped { entries: usize },
+    /// The path matches, but ranks below the result limit
+    BeyondLimit { rank: usize, limit: usize },
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Missing => write!(f, "does not exist"),
+            Reason::NoSearchBase => write!(f, "the directory this query searches does not exist"),
+            Reason::OutsideSearchBase { base } => write!(
+                f,
+                "outside the searched directory {}; start the query with ../ to search higher up",
+                base.display()
+            ),
+            Reason::SkipDir { dir, always: true } => {
+                write!(f, "inside {dir}/, which is never searched")
+            }
+            Reason::SkipDir { dir, always: false } => write!(
+                f,
+                "inside {dir}/, which is skipped by default; start the query with !! to include it"
+            ),
+            Reason::Denied { file, rule } => write!(
+                f,
+                "denied by `{rule}` in {}; Claude Code may not read it",
+                file.display()
+            ),
+            Reason::Sensitive { pattern } => write!(
+                f,
+                "matches the sensitive-file pattern `{pattern}`; set \"sensitive\" to \"flag\" or \"show\" to suggest it"
+            ),
+            Reason::Ignored {
+                file,
+                line,
+                pattern,
+            } => {
+                write!(f, "ignored by rule `{pattern}`")?;
+                if let Some(file) = file {
+                    write!(f, " in {}", file.display())?;
+                    if let Some(line) = line {
+                        write!(f, ":{line}")?;
+                    }
+                }
+                write!(f, "; start the query with !! to include it")
+            }
+            Reason::NestedRepo { dir } => write!(
+                f,
+                "inside the nested repository {dir}/, excluded by the `submodules` setting"
+            ),
+            Reason::TooDeep { depth, max_depth } => write!(
+                f,
+                "{depth} levels deep, beyond the depth limit of {max_depth}"
+            ),
+            Reason::NoMatch => write!(f, "does not match the query"),
+            Reason::WalkStopped { entries } => write!(
+                f,
+                "not found before the walk stopped after {entries} entries; search from a narrower directory or raise `limits.max_entries`"
+            ),
+            Reason::BeyondLimit { rank, limit } => write!(
+                f,
+                "ranked #{rank}, below the result limit of {limit}; a more specific query ranks it higher"
+            ),
+        }
+    }
+}
+
+pub(crate) fn diagnose(search: &Search, path: &Path) -> Diagnosis {
+    let target = normalize(&search.cwd.join(path));
+    let mut diagnosis = Diagnosis {
+        path: path.to_string_lossy().replace('\\', "/"),
+        rank: None,
+        score: None,
+        reasons: Vec::new(),
+    };
+
+    let Ok(metadata) = fs::symlink_metadata(&target) else {
+        diagnosis.reasons.push(Reason::Missing);
+        return diagnosis;
+    };
+    let Some(plan) = search.plan() else {
+        diagnosis.reasons.push(Reason::NoSearchBase);
+        return diagnosis;
+    };
+    // Paths in an additional root are checked against that root
+    let primary = normalize(&plan.parsed.search_base);
+    let (base, root, walk_config) = match plan
+        .roots
+        .iter()
+        .find(|(root, _)| !target.starts_with(&primary) && target.starts_with(&root.dir))
+    {
+        Some((root, config)) => (root.dir.clone(), Some(root), config),
+        None => (primary, None, &plan.walk_config),
+    };
+    let Ok(rel) = target.strip_prefix(&base) else {
+        diagnosis.reasons.push(Reason::OutsideSearchBase { base });
+        return diagnosis;
+    };
+    let rel_str = rel.to_string_lossy().replace('\\', "/");
+    let is_dir = metadata.is_dir();
+    let shown = match root {
+        Some(root) => Some(format!("{}{rel_str}", root.prefix)),
+        None => search::display_path(&plan.parsed, plan.cwd_in_root.as_deref(), &rel_str),
+    };
+    if let Some(shown) = shown {
+        diagnosis.path = shown;
+    }
+
+    // Static reasons, in the order the walk applies them
+    if let Some(reason) = skip_dir(&rel_str, is_dir, plan.include_ignored) {
+        diagnosis.reasons.push(reason);
+    }
+    if search.submodules == SubmodulePolicy::Exclude
+        && let Some(reason) = nested_repo(&base, rel)
+    {
+        diagnosis.reasons.push(reason);
+    }
+    let settings_dir = root.map_or(&search.cwd, |root| &root.dir);
+    if let Some(rule) = DenyRules::load(settings_dir).matched(&target, is_dir) {
+        diagnosis.reasons.push(Reason::Denied {
+            file: rule.file.clone(),
+            rule: rule.rule.clone(),
+        });
+    }
+    if search.sensitive == SensitivePolicy::Hide
+        && let Some(pattern) = search.sensitive_files.matched(&rel_str, is_dir)
+    {
+        diagnosis.reasons.push(Reason::Sensitive {
+            pattern: pattern.to_string(),
+        });
+    }
+    if !plan.include_ignored
+        && let Some(glob) = IgnoreRules::new(&base).ignored(rel)
+    {
+        diagnosis.reasons.push(ignored(&glob));
+    }
+    let depth = rel.components().count();
+    if depth > walk_config.max_depth {
+        diagnosis.reasons.push(Reason::TooDeep {
+            depth,
+            max_depth: walk_config.max_depth,
+        });
+    }
+
+    // The search itself has the final word on rank and score
+    let (all, stats) = search.clone().limit(usize::MAX).run_with_stats();
+    match all.iter().position(|result| result.path == diagnosis.path) {
+        Some(index) => {
+            diagnosis.rank = Some(index + 1);
+            diagnosis.score = Some(all[index].score);
+            if index >= search.limit {
+                diagnosis.reasons.push(Reason::BeyondLimit {
+                    rank: index + 1,
+                    limit: search.limit,
+                });
+            }
+        }
+        None if !diagnosis.reasons.is_empty() => {}
+        None => diagnosis.reasons.push(match walk_config.max_entries {
+            Some(entries) if stats.walk_truncated => Reason::WalkStopped { entries },
+            _ => Reason::NoMatch,
+        }),
+    }
+    diagnosis
+}
+
+/// A `SKIP_DIRS` directory on the way to `rel`, as the walk prunes it.
+fn skip_dir(rel: &str, is_dir: bool, include_ignored: bool) -> Option<Reason> {
+    let parts: Vec<&str> = rel.split('/').collect();
+    // The last part is only pruned if it is a directory (or at the top level)
+    let dirs = if is_dir || parts.len() == 1 {
+        &parts[..]
+    } else {
+        &parts[..parts.len() - 1]
+    };
+    dirs.iter().enumerate().find_map(|(i, part)| {
+        let always = ALWAYS_SKIP_DIRS.contains(part);
+        let skipped = always || (!include_ignored && SKIP_DIRS.contains(part));
+        skipped.then(|| Reason::SkipDir {
+            dir: parts[..=i].join("/"),
+            always,
+        })
+    })
+}
+
+/// The first nested checkout between the search base and `rel`.
+fn nested_repo(base: &Path, rel: &Path) -> Option<Reason> {
+    let registered: Vec<PathBuf> = submodules::nested_repos(base)
+        .into_iter()
+        .map(|repo| repo.work_tree)
+        .collect();
+    let mut dir = base.to_path_buf();
+    for part in rel.parent()?.components() {
+        dir.push(part);
+        if registered.contains(&dir) || dir.join(".git").exists() {
+            let shown = dir.strip_prefix(base).unwrap_or(&dir);
+            return Some(Reason::NestedRepo {
+                dir: shown.to_string_lossy().replace('\\', "/"),
+            });
+        }
+    }
+    None
+}
+
+fn ignored(glob: &Glob) -> Reason {
+    let file = glob.from().map(Path::to_path_buf);
+    let line = file.as_ref().and_then(|file| {
+        let contents = fs::read_to_string(file).ok()?;
+        let lines: Vec<&str> = contents.lines().collect();
+        lines
+            .iter()
+            .rposition(|line| line.trim() == glob.original())
+            .map(|index| index + 1)
+    });
+    Reason::Ignored {
+        file,
+        line,
+        pattern: glob.original().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walker::Limits;
+    use tempfile::TempDir;
+
+    fn create_project() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path();
+        fs::create_dir_all(base.join(".git")).unwrap();
+        fs::create_dir_all(base.join("src/generated")).unwrap();
+        fs::create_dir_all(base.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(base.join("a/b/c/d/e/f")).unwrap();
+        fs::write(
+            base.join(".gitignore"),
+            "# build output\n*.log\nsrc/generated/\n",
+        )
+        .unwrap();
+        fs::write(base.join("src/main.rs"), "").unwrap();
+        fs::write(base.join("src/generated/api.rs"), "").unwrap();
+        fs::write(base.join("debug.log"), "").unwrap();
+        fs::write(base.join("node_modules/pkg/index.js"), "").unwrap();
+        fs::write(base.join("a/b/c/d/e/f/deep.txt"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_suggested_path_has_rank() {
+        let dir = create_project();
+        let diagnosis = Search::new(dir.path())
+            .query("src/main")
+            .diagnose("src/main.rs");
+        assert!(diagnosis.is_suggested(), "{diagnosis:?}");
+        assert_eq!(diagnosis.rank, Some(1));
+        assert!(diagnosis.score.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_gitignore_rule_with_line() {
+        let dir = create_project();
+        let diagnosis = Search::new(dir.path()).query("debug").diagnose("debug.log");
+        assert_eq!(
+            diagnosis.reasons,
+            vec![Reason::Ignored {
+                file: Some(dir.path().join(".gitignore")),
+                line: Some(2),
+                pattern: "*.log".to_string(),
+            }]
+        );
+
+        // A file inside an ignored directory names the directory's rule
+        let diagnosis = Search::new(dir.path())
+            .query("api")
+            .diagnose("src/generated/api.rs");
+        assert!(matches!(
+            &diagnosis.reasons[..],
+            [Reason::Ignored { line: Some(3), .. }]
+        ));
+
+        // Negations in deeper files are honoured
+        fs::write(dir.path().join("src/.gitignore"), "!keep.log\n").unwrap();
+        fs::write(dir.path().join("src/keep.log"), "").unwrap();
+        let diagnosis = Search::new(dir.path())
+            .query("keep")
+            .diagnose("src/keep.log");
+        assert!(diagnosis.is_suggested(), "{diagnosis:?}");
+    }
+
+    #[test]
+    fn test_ignore_precedence_matches_walk() {
+        let dir = create_project();
+        fs::create_dir_all(dir.path().join(".git/info")).unwrap();
+        fs::write(dir.path().join(".git/info/exclude"), "*.tmp\n").unwrap();
+        fs::write(dir.path().join("scratch.tmp"), "").unwrap();
+        let diagnosis = Search::new(dir.path())
+            .query("scratch")
+            .diagnose("scratch.tmp");
+        assert_eq!(
+            diagnosis.reasons,
+            vec![Reason::Ignored {
+                file: Some(dir.path().join(".git/info/exclude")),
+                line: Some(1),
+                pattern: "*.tmp".to_string(),
+            }]
+        );
+
+        // `.ignore` wins over `.gitignore`, as in the walk
+        fs::write(dir.path().join(".ignore"), "!debug.log\n").unwrap();
+        let diagnosis = Search::new(dir.path()).query("debug").diagnose("debug.log");
+        assert!(diagnosis.is_suggested(), "{diagnosis:?}");
+    }
+
+    #[test]
+    fn test_denied_by_claude_settings() {
+        let dir = create_project();
+        let settings = crate::project_settings_path(dir.path());
+        fs::create_dir_all(settings.parent().unwrap()).unwrap();
+        fs::write(
+            &settings,
+            r#"{"permissions": {"deny": ["Read(./src/main.rs)"]}}"#,
+        )
+        .unwrap();
+
+        // Denied even when ignored files are requested
+        let diagnosis = Search::new(dir.path())
+            .query("!!src/main")
+            .diagnose("src/main.rs");
+        assert_eq!(
+            diagnosis.reasons,
+            vec![Reason::Denied {
+                file: settings,
+                rule: "Read(./src/main.rs)".to_string(),
+            }]
+        );
+        assert_eq!(diagnosis.rank, None);
+    }
+
+    #[test]
+    fn test_sensitive_file() {
+        let dir = create_project();
+        fs::write(dir.path().join(".env.local"), "").unwrap();
+        let search = Search::new(dir.path()).query("env");
+        let diagnosis = search.diagnose(".env.local");
+        assert_eq!(
+            diagnosis.reasons,
+            vec![Reason::Sensitive {
+                pattern: ".env.*".to_string()
+            }]
+        );
+
+        let diagnosis = search
+            .sensitive(SensitivePolicy::Flag)
+            .diagnose(".env.local");
+        assert!(diagnosis.is_suggested(), "{diagnosis:?}");
+    }
+
+    #[test]
+    fn test_skip_dir() {
+        let dir = create_project();
+        let diagnosis = Search::new(dir.path())
+            .query("index")
+            .diagnose("node_modules/pkg/index.js");
+        assert_eq!(
+            diagnosis.reasons,
+            vec![Reason::SkipDir {
+                dir: "node_modules".to_string(),
+                always: false,
+            }]
+        );
+
+        // With !! the file is found, ranked below visible results
+        let diagnosis = Search::new(dir.path())
+            .query("!!index")
+            .diagnose("node_modules/pkg/index.js");
+        assert!(diagnosis.is_suggested(), "{diagnosis:?}");
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let dir = create_project();
+        let diagnosis = Search::new(dir.path())
+            .query("deep")
+            .diagnose("a/b/c/d/e/f/deep.txt");
+        assert_eq!(
+            diagnosis.reasons,
+            vec![Reason::TooDeep {
+                depth: 7,
+                max_depth: 6,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_result_limit_and_no_match() {
+        let dir = create_project();
+        for i in 0..5 {
+            fs::write(dir.path().join(format!("src/main{i}.rs")), "").unwrap();
+        }
+        let diagnosis = Search::new(dir.path())
+            .query("src/main")
+            .limit(2)
+            .diagnose("src/main4.rs");
+        assert!(matches!(
+            &diagnosis.reasons[..],
+            [Reason::BeyondLimit { limit: 2, .. }]
+        ));
+        assert!(diagnosis.rank.unwrap() > 2);
+
+        let diagnosis = Search::new(dir.path()).query("xyz").diagnose("src/main.rs");
+        assert_eq!(diagnosis.reasons, vec![Reason::NoMatch]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_broad_walk_stopped() {
+        // Walks from / are capped, and /etc comes after /bin in name order
+        let limits = Limits {
+            max_entries: 1,
+            ..Limits::default()
+        };
+        let diagnosis = Search::new("/").query("etc").limits(limits).diagnose("etc");
+        assert_eq!(diagnosis.reasons, vec![Reason::WalkStopped { entries: 1 }]);
+
+        let diagnosis = Search::new("/").query("etc").diagnose("etc");
+        assert!(diagnosis.rank.is_some(), "{diagnosis:?}");
+    }
+
+    #[test]
+    fn test_missing_and_outside() {
+        let dir = create_project();
+        let diagnosis = Search::new(dir.path()).query("x").diagnose("nope.rs");
+        assert_eq!(diagnosis.reasons, vec![Reason::Missing]);
+
+        let diagnosis = Search::new(dir.path().join("src"))
+            .query("debug")
+            .diagnose("../debug.log");
+        assert!(matches!(
+            &diagnosis.reasons[..],
+            [Reason::OutsideSearchBase { .. }]
+        ));
+
+        // With ../ in the query the path is reachable, and shown as suggested
+        let diagnosis = Search::new(dir.path().join("src"))
+            .query("../READ")
+            .diagnose("../debug.log");
+        assert_eq!(diagnosis.path, "../debug.log");
+        assert!(matches!(&diagnosis.reasons[..], [Reason::Ignored { .. }]));
+    }
+
+    #[test]
+    fn test_nested_repo_excluded() {
+        let dir = create_project();
+        fs::create_dir_all(dir.path().join("vendor/zlib/.git")).unwrap();
+        fs::write(dir.path().join("vendor/zlib/zlib.h"), "").unwrap();
+        let diagnosis = Search::new(dir.path())
+            .submodules(SubmodulePolicy::Exclude)
+            .query("zlib")
+            .diagnose("vendor/zlib/zlib.h");
+        assert_eq!(
+            diagnosis.reasons,
+            vec![Reason::NestedRepo {
+                dir: "vendor/zlib".to_string(),
+            }]
+        );
+    }
+}
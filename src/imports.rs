@@ -0,0 +1,554 @@
+//! Import paths as written in code, mapped to the files defining them:
+//! Rust module paths (`crate::walker::walk_files`), Python modules
+//! (`myapp.services.billing`), Java and Kotlin names (`com.acme.Foo`) and
+//! TypeScript aliases from `tsconfig.json` (`@/components/Button`).
+
+use crate::project;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where Python and JVM sources live below a project root, "" for the root
+const SOURCE_DIRS: &[&str] = &[
+    "",
+    "src",
+    "lib",
+    "src/main/java",
+    "src/test/java",
+    "src/main/kotlin",
+    "src/test/kotlin",
+];
+
+/// Suffixes tried for a dotted module path, in order
+const DOTTED_SUFFIXES: &[&str] = &[".py", "/__init__.py", ".pyi", ".java", ".kt"];
+
+/// Suffixes tried for a TypeScript alias target, as the compiler does
+const TS_SUFFIXES: &[&str] = &[
+    "",
+    ".ts",
+    ".tsx",
+    ".d.ts",
+    ".js",
+    ".jsx",
+    ".mjs",
+    ".cjs",
+    ".vue",
+    ".svelte",
+    "/index.ts",
+    "/index.tsx",
+    "/index.js",
+    "/index.jsx",
+];
+
+/// Config files that may declare path aliases
+const TS_CONFIGS: &[&str] = &["tsconfig.json", "jsconfig.json"];
+
+/// Whether `pattern` is shaped like an import path, checked before touching
+/// the filesystem so plain queries such as "main" cost nothing: a Rust path
+/// ("a::b"), three or more dotted names ("myapp.services.billing"), or a
+/// path that could start with a TypeScript alias ("@/x", "~/x", "lib/x").
+pub(crate) fn looks_like_import(pattern: &str) -> bool {
+    pattern.contains("::") || is_dotted(pattern) || could_be_alias(pattern)
+}
+
+/// Files the import path in `pattern` refers to, most specific first. Project
+/// files (`Cargo.toml`, `tsconfig.json`) are looked up from `base` to the
+/// project root. Returns nothing for patterns that are not import paths.
+pub(crate) fn resolve(pattern: &str, base: &Path) -> Vec<PathBuf> {
+    let alias = could_be_alias(pattern) && !names_entry(pattern, base);
+    if !pattern.contains("::") && !is_dotted(pattern) && !alias {
+        return Vec::new();
+    }
+    let root = project::find_root(base);
+    let dirs: Vec<&Path> = project_dirs(base, root.as_deref()).collect();
+
+    let mut files = if pattern.contains("::") {
+        rust_module(pattern, &dirs)
+    } else if is_dotted(pattern) {
+        dotted_module(pattern, base, root.as_deref())
+    } else {
+        Vec::new()
+    };
+    if alias {
+        files.extend(ts_alias(pattern, &dirs));
+    }
+
+    let mut unique = Vec::with_capacity(files.len());
+    for file in files {
+        if !unique.contains(&file) {
+            unique.push(file);
+        }
+    }
+    unique
+}
+
+/// `base` and its ancestors up to the project root (all of them if there is
+/// no project).
+fn project_dirs<'a>(base: &'a Path, root: Option<&Path>) -> impl Iterator<Item = &'a Path> {
+    let depth = root
+        .and_then(|root| base.strip_prefix(root).ok())
+        .map_or(usize::MAX, |rel| rel.components().count() + 1);
+    base.ancestors().take(depth)
+}
+
+/// The longest module prefix of a Rust path that is a file in the enclosing
+/// crate. `crate::`, `self::` and `super::` (taken from the crate root, as
+/// the current file is unknown) and the crate's own name are stripped.
+fn rust_module(pattern: &str, dirs: &[&Path]) -> Vec<PathBuf> {
+    let Some(manifest_dir) = dirs.iter().find(|dir| dir.join("Cargo.toml").is_file()) else {
+        return Vec::new();
+    };
+    // "walker::{walk_files, Entry}" names the walker module
+    let segments: Vec<&str> = pattern
+        .trim_start_matches("::")
+        .split("::")
+        .take_while(|segment| is_identifier(segment))
+        .collect();
+    let crate_name = package_name(&manifest_dir.join("Cargo.toml"));
+    let skip = match segments.first() {
+        Some(&"crate") => 1,
+        Some(first) if crate_name.is_some_and(|name| name.replace('-', "_") == *first) => 1,
+        _ => 0,
+    };
+    let modules: Vec<&str> = segments[skip..]
+        .iter()
+        .copied()
+        .skip_while(|segment| matches!(*segment, "self" | "super"))
+        .collect();
+
+    let src = manifest_dir.join("src");
+    for len in (1..=modules.len()).rev() {
+        let module = modules[..len].join("/");
+        for candidate in [
+            src.join(format!("{module}.rs")),
+            src.join(&module).join("mod.rs"),
+        ] {
+            if candidate.is_file() {
+                return vec![candidate];
+            }
+        }
+    }
+    // Only items of the crate root, e.g. "crate::Search"
+    if skip > 0 {
+        for root_file in ["lib.rs", "main.rs"] {
+            let candidate = src.join(root_file);
+            if candidate.is_file() {
+                return vec![candidate];
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// The `name` in a manifest's `[package]` table.
+fn package_name(manifest: &Path) -> Option<String> {
+    let contents = fs::read_to_string(manifest).ok()?;
+    let mut in_package = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if in_package
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim() == "name"
+        {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+/// Whether `pattern` is three or more identifiers joined by dots, as in
+/// "myapp.services.billing" or "com.acme.Foo", not ending in something like
+/// an extension. File names ("main.rs", "app.config.ts") are left to fuzzy
+/// matching.
+fn is_dotted(pattern: &str) -> bool {
+    let segments: Vec<&str> = pattern.split('.').collect();
+    segments.len() >= 3
+        && segments.iter().all(|segment| is_identifier(segment))
+        && segments.last().is_some_and(|tail| !is_extension_like(tail))
+}
+
+/// Short lowercase names such as "rs", "json" or "tsx".
+fn is_extension_like(segment: &str) -> bool {
+    segment.len() <= 4
+        && segment
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+}
+
+/// Whether `pattern` could start with a `paths` alias prefix: a word
+/// followed by `/`, or a leading `@`, `~`, `#` or `$` as aliases use.
+fn could_be_alias(pattern: &str) -> bool {
+    !pattern.contains(char::is_whitespace)
+        && (pattern.contains('/') || pattern.starts_with(['@', '~', '#', '$']))
+}
+
+/// Whether the first segment of a path query names an entry of `base`, as
+/// "src" in "src/main" does. Such queries are paths, so the project root and
+/// tsconfig.json are not looked up for them; one `stat` decides.
+fn names_entry(pattern: &str, base: &Path) -> bool {
+    pattern
+        .split_once('/')
+        .is_some_and(|(first, _)| !first.is_empty() && base.join(first).exists())
+}
+
+fn is_identifier(segment: &str) -> bool {
+    let mut chars = segment.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Python modules and packages, Java and Kotlin classes: the longest prefix
+/// of at least two names that is a file below `base` or the project root.
+fn dotted_module(pattern: &str, base: &Path, root: Option<&Path>) -> Vec<PathBuf> {
+    let segments: Vec<&str> = pattern.split('.').collect();
+    let mut dirs = vec![base];
+    dirs.extend(root.filter(|root| *root != base));
+
+    for len in (2..=segments.len()).rev() {
+        let module = segments[..len].join("/");
+        let mut found = Vec::new();
+        for dir in &dirs {
+            for source in SOURCE_DIRS {
+                for suffix in DOTTED_SUFFIXES {
+                    let candidate = dir.join(source).join(format!("{module}{suffix}"));
+                    if candidate.is_file() {
+                        found.push(candidate);
+                    }
+                }
+            }
+        }
+        if !found.is_empty() {
+            return found;
+        }
+    }
+    Vec::new()
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TsConfig {
+    compiler_options: CompilerOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct CompilerOptions {
+    base_url: Option<String>,
+    /// Alias patterns to target patterns, in file order
+    paths: Map<String, Value>,
+}
+
+/// Targets of the `paths` alias in the nearest tsconfig.json (or
+/// jsconfig.json) matching `pattern`. As in TypeScript, the alias with the
+/// longest prefix before its `*` wins, and targets are tried in order. A
+/// bare `"*"` alias would pin files for every query, so it is not used.
+fn ts_alias(pattern: &str, dirs: &[&Path]) -> Vec<PathBuf> {
+    let Some(config_path) = dirs
+        .iter()
+        .flat_map(|dir| TS_CONFIGS.iter().map(|name| dir.join(name)))
+        .find(|path| path.is_file())
+    else {
+        return Vec::new();
+    };
+    let Some(config) = fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|text| serde_json::from_str::<TsConfig>(&strip_jsonc(&text)).ok())
+    else {
+        return Vec::new();
+    };
+    let options = config.compiler_options;
+    let config_dir = config_path.parent().unwrap_or(Path::new(""));
+    let base_url = config_dir.join(options.base_url.as_deref().unwrap_or("."));
+
+    // Only aliases starting with the query's first segment can match
+    let first = pattern.split('/').next().unwrap_or_default();
+    let mut best: Option<(usize, &str, &Value)> = None;
+    for (alias, targets) in &options.paths {
+        if alias.split(['/', '*']).next() != Some(first) {
+            continue;
+        }
+        let matched = match alias.split_once('*') {
+            Some(("", _)) => None,
+            Some((prefix, suffix)) => pattern
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.strip_suffix(suffix))
+                .map(|captured| (prefix.len(), captured)),
+            None => (pattern == alias).then_some((alias.len(), "")),
+        };
+        if let Some((specificity, captured)) = matched
+            && best.is_none_or(|(longest, _, _)| specificity > longest)
+        {
+            best = Some((specificity, captured, targets));
+        }
+    }
+    let Some((_, captured, targets)) = best else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for target in targets.as_array().into_iter().flatten() {
+        let Some(target) = target.as_str() else {
+            continue;
+        };
+        let target = target.replacen('*', captured, 1);
+        let found = TS_SUFFIXES
+            .iter()
+            .map(|suffix| project::normalize(&base_url.join(format!("{target}{suffix}"))))
+            .find(|candidate| candidate.is_file());
+        files.extend(found);
+    }
+    files
+}
+
+/// tsconfig files are JSON with comments and trailing commas.
+fn strip_jsonc(text: &str) -> String {
+    let mut without_comments = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            without_comments.push(c);
+            match c {
+                '\\' => without_comments.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => while chars.next_if(|&next| next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            _ => {
+                in_string = c == '"';
+                without_comments.push(c);
+            }
+        }
+    }
+
+    let mut json = String::with_capacity(without_comments.len());
+    let mut chars = without_comments.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            json.push(c);
+            match c {
+                '\\' => json.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        if c == ',' {
+            let next = chars.clone().find(|next| !next.is_whitespace());
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        in_string = c == '"';
+        json.push(c);
+    }
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn project(files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    fn resolved(pattern: &str, base: &Path) -> Vec<String> {
+        resolve(pattern, base)
+            .iter()
+            .map(|path| {
+                let rel = path.strip_prefix(base).unwrap_or(path);
+                rel.to_string_lossy().replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_module_paths() {
+        let dir = project(&[
+            ("Cargo.toml", "[package]\nname = \"claude-search\"\n"),
+            ("src/lib.rs", ""),
+            ("src/walker.rs", ""),
+            ("src/net/mod.rs", ""),
+            ("src/net/http.rs", ""),
+        ]);
+        let base = dir.path();
+        assert_eq!(
+            resolved("crate::walker::walk_files", base),
+            ["src/walker.rs"]
+        );
+        assert_eq!(
+            resolved("claude_search::walker::Entry", base),
+            ["src/walker.rs"]
+        );
+        assert_eq!(resolved("crate::net::http::get", base), ["src/net/http.rs"]);
+        assert_eq!(resolved("super::net::Client", base), ["src/net/mod.rs"]);
+        assert_eq!(
+            resolved("crate::walker::{walk_files, Entry}", base),
+            ["src/walker.rs"]
+        );
+        assert_eq!(resolved("crate::Search", base), ["src/lib.rs"]);
+        assert!(resolved("std::collections::HashMap", base).is_empty());
+
+        // From a subdirectory, the enclosing crate is found
+        let src = base.join("src");
+        assert_eq!(resolved("crate::walker", &src), ["walker.rs"]);
+    }
+
+    #[test]
+    fn test_python_modules() {
+        let dir = project(&[
+            ("pyproject.toml", ""),
+            ("src/myapp/__init__.py", ""),
+            ("src/myapp/services/__init__.py", ""),
+            ("src/myapp/services/billing.py", ""),
+            ("main.py", ""),
+        ]);
+        let base = dir.path();
+        assert_eq!(
+            resolved("myapp.services.billing", base),
+            ["src/myapp/services/billing.py"]
+        );
+        // Names after the module are functions or classes
+        assert_eq!(
+            resolved("myapp.services.billing.charge_card", base),
+            ["src/myapp/services/billing.py"]
+        );
+        assert_eq!(
+            resolved("myapp.services.__init__", base),
+            ["src/myapp/services/__init__.py"]
+        );
+        // A file name, or a single dot, is not a module path
+        assert!(resolved("main.py", base).is_empty());
+        assert!(resolved("myapp.services", base).is_empty());
+        assert!(resolved("myapp.services.py", base).is_empty());
+    }
+
+    #[test]
+    fn test_java_names() {
+        let dir = project(&[
+            ("pom.xml", ""),
+            ("src/main/java/com/acme/Foo.java", ""),
+            ("src/test/kotlin/com/acme/FooTest.kt", ""),
+        ]);
+        let base = dir.path();
+        assert_eq!(
+            resolved("com.acme.Foo", base),
+            ["src/main/java/com/acme/Foo.java"]
+        );
+        assert_eq!(
+            resolved("com.acme.Foo.render", base),
+            ["src/main/java/com/acme/Foo.java"]
+        );
+        assert_eq!(
+            resolved("com.acme.FooTest", base),
+            ["src/test/kotlin/com/acme/FooTest.kt"]
+        );
+    }
+
+    #[test]
+    fn test_typescript_aliases() {
+        let tsconfig = r#"{
+            // Comments and trailing commas are allowed
+            "compilerOptions": {
+                "baseUrl": ".",
+                "paths": {
+                    "@/*": ["src/*"],
+                    "@components/*": ["src/ui/components/*", "legacy/components/*",],
+                    "$config": ["src/config/index.ts"], /* exact alias */
+                    "*": ["src/*"],
+                },
+            },
+        }"#;
+        let dir = project(&[
+            ("tsconfig.json", tsconfig),
+            ("src/components/Button.tsx", ""),
+            ("src/ui/components/Card/index.tsx", ""),
+            ("legacy/components/Card.js", ""),
+            ("src/config/index.ts", ""),
+        ]);
+        let base = dir.path();
+        assert_eq!(
+            resolved("@/components/Button", base),
+            ["src/components/Button.tsx"]
+        );
+        // The longer alias prefix wins; every target that exists is kept
+        assert_eq!(
+            resolved("@components/Card", base),
+            [
+                "src/ui/components/Card/index.tsx",
+                "legacy/components/Card.js"
+            ]
+        );
+        assert_eq!(resolved("$config", base), ["src/config/index.ts"]);
+        assert!(resolved("@/missing", base).is_empty());
+        // Plain queries are not aliases, not even for "*"
+        assert!(resolved("Button", base).is_empty());
+        assert!(resolved("components/Button", base).is_empty());
+    }
+
+    #[test]
+    fn test_path_queries_skip_aliases() {
+        let tsconfig =
+            r#"{"compilerOptions": {"paths": {"src/*": ["lib/*"], "app/*": ["lib/*"]}}}"#;
+        let dir = project(&[
+            ("tsconfig.json", tsconfig),
+            ("src/main.ts", ""),
+            ("lib/main.ts", ""),
+        ]);
+        let base = dir.path();
+        // "src" is a directory here, so "src/main" is a path, not an alias
+        assert!(resolved("src/main", base).is_empty());
+        assert_eq!(resolved("app/main", base), ["lib/main.ts"]);
+    }
+
+    #[test]
+    fn test_looks_like_import() {
+        for pattern in [
+            "crate::walker",
+            "a.b.Foo",
+            "@/x",
+            "~/lib",
+            "#config",
+            "lib/x",
+        ] {
+            assert!(looks_like_import(pattern), "{pattern}");
+        }
+        for pattern in ["main", "main.rs", "app.config.ts", "a.b", "", "a b"] {
+            assert!(!looks_like_import(pattern), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_strip_jsonc() {
+        let text = "{\"a\": \"// not a comment\", /* x */ \"b\": [1, 2,], // end\n}";
+        let value: Value = serde_json::from_str(&strip_jsonc(text)).unwrap();
+        assert_eq!(value["a"], "// not a comment");
+        assert_eq!(value["b"], serde_json::json!([1, 2]));
+    }
+}
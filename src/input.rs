@@ -1,13 +1,50 @@
-use serde::Deserialize;
+use crate::output::OutputFormat;
+use crate::project::SearchRoot;
+use crate::sensitive::SensitivePolicy;
+use crate::submodules::SubmodulePolicy;
+use crate::walker::SymlinkPolicy;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Deserialize)]
+/// Query prefix that disables gitignore and skip-dir filtering (e.g. "!!target/debug")
+const INCLUDE_IGNORED_PREFIX: &str = "!!";
+
+/// Scope token restricting the search to one submodule (e.g. "sub:libfoo main")
+const SUBMODULE_PREFIX: &str = "sub:";
+
+/// Scope token restricting the search to one root (e.g. "root:api handler")
+const ROOT_PREFIX: &str = "root:";
+
+/// A request as sent by Claude Code on stdin.
+#[derive(Debug, Default, Deserialize)]
+#[non_exhaustive]
 pub struct Input {
     pub query: Option<String>,
     pub cwd: Option<String>,
+    /// Maximum number of results, defaults to 50
+    pub limit: Option<usize>,
+    /// Also search gitignored files and skipped directories (same as a leading "!!")
+    #[serde(default)]
+    pub include_ignored: bool,
+    /// Output format, defaults to plain lines
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Symlink policy, overrides the config file
+    pub symlinks: Option<SymlinkPolicy>,
+    /// Submodule policy, overrides the config file
+    pub submodules: Option<SubmodulePolicy>,
+    /// Search root, overrides the config file
+    pub search_root: Option<SearchRoot>,
+    /// Sensitive-file policy, overrides the config file
+    pub sensitive: Option<SensitivePolicy>,
+    /// Directories searched alongside `cwd`, added to those in the config file
+    #[serde(default)]
+    pub additional_roots: Vec<String>,
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct ParsedQuery {
     /// The actual pattern to match (e.g., "main" from "../src/main")
     pub pattern: String,
@@ -17,6 +54,47 @@ pub struct ParsedQuery {
     pub output_prefix: String,
     /// Whether this is an empty query (shallow listing mode)
     pub is_empty: bool,
+    /// Whether the query asked for ignored files (leading "!!")
+    pub include_ignored: bool,
+    /// Submodule named by a leading "sub:name" token
+    pub submodule: Option<String>,
+    /// Search root named by a leading "root:name" token
+    pub root: Option<String>,
+    /// A URL or absolute path pasted from elsewhere, matched against local
+    /// files by its longest suffix
+    pub pasted: Option<PastedPath>,
+}
+
+/// A path pasted from a code-hosting URL, a CI log or another machine, e.g.
+/// "https://github.com/org/repo/blob/main/src/x.rs#L10" or
+/// "/home/ci/build/repo/src/x.rs:10".
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PastedPath {
+    /// The path without scheme, host, branch or leading `/`, e.g.
+    /// "src/x.rs" or "home/ci/build/repo/src/x.rs"
+    pub path: String,
+    pub line: Option<LineAnchor>,
+}
+
+/// A line or line range attached to a pasted path ("#L10-L20", ":10:5").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct LineAnchor {
+    pub start: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<u32>,
+}
+
+/// Written as a fragment, "#L10" or "#L10-20".
+impl fmt::Display for LineAnchor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#L{}", self.start)?;
+        if let Some(end) = self.end {
+            write!(f, "-{end}")?;
+        }
+        Ok(())
+    }
 }
 
 /// Parse a query string and extract the ../ prefix chain.
@@ -26,55 +104,272 @@ pub struct ParsedQuery {
 /// - "../foo" -> pattern="foo", prefix="../", search from cwd/..
 /// - "../../bar" -> pattern="bar", prefix="../../", search from cwd/../..
 /// - "./src" -> pattern="src", prefix="", search from cwd
+/// - "./../x" or "../src/../x" -> pattern="x", prefix="../"
+/// - "!!target/debug" -> pattern="target/debug", include ignored files
+/// - "sub:libfoo main" -> pattern="main", scoped to the libfoo submodule
+/// - "root:api main" -> pattern="main", scoped to the additional root "api"
+/// - "..\src\main" or ".\lib" -> as with "/" (Windows separators)
+/// - "C:\src\main" -> pattern="C:/src/main", a drive path is only text
+/// - "https://github.com/o/r/blob/main/src/x.rs#L10" -> pattern="src/x.rs",
+///   pasted path "src/x.rs" at line 10
+/// - "/home/ci/repo/src/x.rs:10" -> pattern="/home/ci/repo/src/x.rs",
+///   pasted path "home/ci/repo/src/x.rs" at line 10
 pub fn parse_query(raw_query: &str, cwd: &Path) -> ParsedQuery {
-    let query = raw_query.trim();
+    // Accept Windows separators, e.g. "..\src\main" pasted from a log
+    let normalized = normalize_separators(raw_query.trim());
+    let mut query = normalized.as_str();
 
-    // Count and extract ../ prefix chain
-    let mut prefix_count = 0;
-    let mut remaining = query;
+    // Handle leading !! (include ignored files)
+    let include_ignored = match query.strip_prefix(INCLUDE_IGNORED_PREFIX) {
+        Some(stripped) => {
+            query = stripped.trim_start();
+            true
+        }
+        None => false,
+    };
 
-    // Handle leading ./
-    if let Some(stripped) = remaining.strip_prefix("./") {
-        remaining = stripped;
-    }
+    // Handle leading root:name, then sub:name
+    let root = strip_scope(&mut query, ROOT_PREFIX);
+    let submodule = strip_scope(&mut query, SUBMODULE_PREFIX);
 
-    // Count ../ prefixes
-    while let Some(stripped) = remaining.strip_prefix("../") {
-        prefix_count += 1;
-        remaining = stripped;
+    // A pasted URL is reduced to its path within the repository
+    if let Some((path, line)) = parse_url(query) {
+        let (_, pattern) = resolve_dots(&path);
+        return ParsedQuery {
+            is_empty: pattern.is_empty(),
+            pasted: (!pattern.is_empty()).then(|| PastedPath {
+                path: pattern.clone(),
+                line,
+            }),
+            pattern,
+            search_base: cwd.to_path_buf(),
+            output_prefix: String::new(),
+            include_ignored,
+            submodule,
+            root,
+        };
     }
 
-    // Handle trailing .. without /
-    if remaining == ".." {
-        prefix_count += 1;
-        remaining = "";
-    }
+    // An absolute path from a log keeps its text, less any line anchor
+    let line = if query.starts_with('/') || has_drive_prefix(query) {
+        strip_line_anchor(&mut query)
+    } else {
+        None
+    };
 
-    // Build output prefix (e.g., "../../")
-    let output_prefix = "../".repeat(prefix_count);
+    // Resolve ./ and ../ segments, counting the ascents past cwd. Drive
+    // paths such as "C:\Users\..\x" mean nothing here and are matched
+    // as text
+    let (ascents, pattern) = if has_drive_prefix(query) {
+        (0, query.to_string())
+    } else {
+        resolve_dots(query)
+    };
+    let pasted = foreign_path(&pattern).map(|path| PastedPath { path, line });
 
-    // Build search base by going up directories
-    let mut search_base = cwd.to_path_buf();
-    for _ in 0..prefix_count {
-        if let Some(parent) = search_base.parent() {
-            search_base = parent.to_path_buf();
-        }
-    }
+    // Build search base by going up directories, stopping at the root
+    let ascents = ascents.min(cwd.ancestors().count() - 1);
+    let search_base = cwd.ancestors().nth(ascents).unwrap_or(cwd).to_path_buf();
 
-    // Strip leading ./ from remaining pattern
-    let pattern = remaining
-        .strip_prefix("./")
-        .unwrap_or(remaining)
-        .to_string();
+    // Build output prefix (e.g., "../../")
+    let output_prefix = "../".repeat(ascents);
 
     ParsedQuery {
         is_empty: pattern.is_empty(),
         pattern,
         search_base,
         output_prefix,
+        include_ignored,
+        submodule,
+        root,
+        pasted,
+    }
+}
+
+/// Split a URL such as "https://github.com/org/repo/blob/main/src/x.rs#L10"
+/// into the path below the branch ("src/x.rs") and its line anchor. The
+/// host is dropped, as are the owner, repository and branch when a forge
+/// marker ("blob", "tree", "raw", "blame") shows where they end; otherwise
+/// the whole URL path is kept and matched by suffix.
+fn parse_url(query: &str) -> Option<(String, Option<LineAnchor>)> {
+    let (scheme, rest) = query.split_once("://")?;
+    let mut chars = scheme.chars();
+    // One letter is a drive, as in "C://x"
+    if scheme.len() < 2
+        || !chars.next()?.is_ascii_alphabetic()
+        || !chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        || rest.contains(char::is_whitespace)
+    {
+        return None;
+    }
+    let (rest, fragment) = rest.split_once('#').unwrap_or((rest, ""));
+    let rest = rest.split_once('?').map_or(rest, |(path, _)| path);
+    let path = rest.split_once('/').map_or("", |(_, path)| path);
+
+    let segments: Vec<&str> = path.split('/').collect();
+    let start = segments
+        .iter()
+        .enumerate()
+        .skip(2)
+        .find(|(_, s)| matches!(**s, "blob" | "tree" | "raw" | "blame"))
+        .map_or(0, |(i, _)| i + 2);
+    let path = segments.get(start..).unwrap_or_default().join("/");
+    Some((percent_decode(&path), parse_fragment(fragment)))
+}
+
+/// Read "L10", "L10-L20", "L10-20" or "L10C5" (GitHub and GitLab style).
+fn parse_fragment(fragment: &str) -> Option<LineAnchor> {
+    let rest = fragment.strip_prefix('L')?;
+    let (start, rest) = split_number(rest)?;
+    let end = rest
+        .strip_prefix('-')
+        .map(|rest| rest.strip_prefix('L').unwrap_or(rest))
+        .and_then(split_number)
+        .map(|(end, _)| end)
+        .filter(|&end| end > start);
+    Some(LineAnchor { start, end })
+}
+
+/// Take a trailing ":10" or ":10:5" (line and column, as compilers print
+/// them) or "#L10" off `query`.
+fn strip_line_anchor(query: &mut &str) -> Option<LineAnchor> {
+    if let Some((path, fragment)) = query.rsplit_once('#')
+        && let Some(line) = parse_fragment(fragment)
+    {
+        *query = path;
+        return Some(line);
+    }
+    let trimmed = query.trim_end_matches(':');
+    let mut numbers = Vec::new();
+    let mut rest = trimmed;
+    while numbers.len() < 2
+        && let Some((head, tail)) = rest.rsplit_once(':')
+        && !tail.is_empty()
+        && tail.bytes().all(|b| b.is_ascii_digit())
+    {
+        numbers.push(tail);
+        rest = head;
+    }
+    let line = numbers.last()?.parse().ok()?;
+    *query = rest;
+    Some(LineAnchor {
+        start: line,
+        end: None,
+    })
+}
+
+/// A leading run of digits and what follows it.
+fn split_number(text: &str) -> Option<(u32, &str)> {
+    let end = text
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len());
+    Some((text[..end].parse().ok()?, &text[end..]))
+}
+
+/// The segments of an absolute path from another machine, e.g.
+/// "/home/ci/repo/src/x.rs" or "C:/build/src/x.rs", as a relative path.
+/// Needs two named segments: "/etc" or "/src" alone stay fuzzy patterns.
+fn foreign_path(pattern: &str) -> Option<String> {
+    let rest = match pattern.strip_prefix('/') {
+        Some(rest) => rest.to_string(),
+        None if has_drive_prefix(pattern) => resolve_dots(&pattern[2..]).1,
+        None => return None,
+    };
+    let segments: Vec<&str> = rest
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != "..")
+        .collect();
+    (segments.len() >= 2).then(|| segments.join("/"))
+}
+
+/// Decode %XX escapes, keeping the text as is where they do not form UTF-8.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| text.to_string())
+}
+
+/// Replace `\` separators with `/`. A backslash before a space is left
+/// alone: nucleo reads it as an escaped space.
+fn normalize_separators(query: &str) -> String {
+    let mut normalized = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() != Some(&' ') => normalized.push('/'),
+            c => normalized.push(c),
+        }
+    }
+    normalized
+}
+
+/// Whether a query starts with a Windows drive, as in "C:/src" or "d:".
+fn has_drive_prefix(query: &str) -> bool {
+    match query.as_bytes() {
+        [letter, b':', rest @ ..] => {
+            letter.is_ascii_alphabetic() && matches!(rest.first(), None | Some(b'/'))
+        }
+        _ => false,
     }
 }
 
+/// Take a leading scope token such as "sub:name" off `query`. The name ends
+/// at whitespace or the first `/`.
+fn strip_scope(query: &mut &str, prefix: &str) -> Option<String> {
+    let scoped = query.strip_prefix(prefix)?;
+    let end = scoped
+        .find(|c: char| c.is_whitespace() || c == '/')
+        .unwrap_or(scoped.len());
+    if end == 0 {
+        return None;
+    }
+    *query = scoped[end..].trim_start_matches('/').trim_start();
+    Some(scoped[..end].to_string())
+}
+
+/// Lexically resolve `.` and `..` segments, e.g. "./../x", "src/../lib" or
+/// "../foo/..". Returns how many levels the query climbs above cwd and the
+/// pattern that remains. Other segments, including empty ones as in "/etc" or
+/// "src/", are kept as typed, and so is a final "." (the start of ".env").
+fn resolve_dots(query: &str) -> (usize, String) {
+    let mut ascents = 0;
+    let mut segments: Vec<&str> = Vec::new();
+    let last = query.split('/').count() - 1;
+    for (i, segment) in query.split('/').enumerate() {
+        match segment {
+            "." if i < last => {}
+            // A slash right after a resolved prefix, as in "..//x" or "./"
+            "" if i > 0 && segments.is_empty() => {}
+            ".." => match segments.last() {
+                Some(&previous) if !previous.is_empty() && previous != ".." => {
+                    segments.pop();
+                }
+                Some(_) => segments.push(segment),
+                None => ascents += 1,
+            },
+            _ => segments.push(segment),
+        }
+    }
+    (ascents, segments.join("/"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -164,6 +459,266 @@ mod tests {
         let input: Input = serde_json::from_str(json).unwrap();
         assert_eq!(input.query, None);
         assert_eq!(input.cwd, None);
+        assert_eq!(input.limit, None);
+        assert!(!input.include_ignored);
+        assert_eq!(input.format, OutputFormat::Lines);
+        assert_eq!(input.symlinks, None);
+        assert_eq!(input.search_root, None);
+        assert_eq!(input.sensitive, None);
+    }
+
+    #[test]
+    fn test_json_symlink_policy() {
+        let json = r#"{"symlinks": "anywhere"}"#;
+        let input: Input = serde_json::from_str(json).unwrap();
+        assert_eq!(input.symlinks, Some(SymlinkPolicy::Anywhere));
+    }
+
+    #[test]
+    fn test_json_search_root() {
+        let json = r#"{"search_root": "project"}"#;
+        let input: Input = serde_json::from_str(json).unwrap();
+        assert_eq!(input.search_root, Some(SearchRoot::Project));
+    }
+
+    #[test]
+    fn test_json_include_ignored_and_format() {
+        let json = r#"{"query": "env", "include_ignored": true, "format": "json"}"#;
+        let input: Input = serde_json::from_str(json).unwrap();
+        assert!(input.include_ignored);
+        assert_eq!(input.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn test_include_ignored_prefix() {
+        let parsed = parse_query("!!.env.local", Path::new("/home/user/project"));
+        assert_eq!(parsed.pattern, ".env.local");
+        assert!(parsed.include_ignored);
+        assert!(!parsed.is_empty);
+    }
+
+    #[test]
+    fn test_include_ignored_prefix_with_parent() {
+        let parsed = parse_query("!!../target", Path::new("/home/user/project"));
+        assert_eq!(parsed.pattern, "target");
+        assert_eq!(parsed.output_prefix, "../");
+        assert!(parsed.include_ignored);
+    }
+
+    #[test]
+    fn test_include_ignored_prefix_alone() {
+        let parsed = parse_query("!!", Path::new("/home/user/project"));
+        assert_eq!(parsed.pattern, "");
+        assert!(parsed.is_empty);
+        assert!(parsed.include_ignored);
+    }
+
+    #[test]
+    fn test_submodule_scope() {
+        let parsed = parse_query("sub:libfoo main", Path::new("/home/user/project"));
+        assert_eq!(parsed.submodule.as_deref(), Some("libfoo"));
+        assert_eq!(parsed.pattern, "main");
+    }
+
+    #[test]
+    fn test_submodule_scope_with_slash() {
+        let parsed = parse_query("!!sub:libfoo/src/lib", Path::new("/home/user/project"));
+        assert_eq!(parsed.submodule.as_deref(), Some("libfoo"));
+        assert_eq!(parsed.pattern, "src/lib");
+        assert!(parsed.include_ignored);
+    }
+
+    #[test]
+    fn test_submodule_scope_alone() {
+        let parsed = parse_query("sub:libfoo", Path::new("/home/user/project"));
+        assert_eq!(parsed.submodule.as_deref(), Some("libfoo"));
+        assert!(parsed.is_empty);
+    }
+
+    #[test]
+    fn test_empty_submodule_scope_is_pattern() {
+        let parsed = parse_query("sub:", Path::new("/home/user/project"));
+        assert_eq!(parsed.submodule, None);
+        assert_eq!(parsed.pattern, "sub:");
+    }
+
+    #[test]
+    fn test_root_scope() {
+        let parsed = parse_query("root:api src/main", Path::new("/home/user/web"));
+        assert_eq!(parsed.root.as_deref(), Some("api"));
+        assert_eq!(parsed.submodule, None);
+        assert_eq!(parsed.pattern, "src/main");
+        assert_eq!(parsed.search_base, Path::new("/home/user/web"));
+    }
+
+    #[test]
+    fn test_root_and_submodule_scope() {
+        let parsed = parse_query("!!root:api/sub:vendor main", Path::new("/home/user/web"));
+        assert_eq!(parsed.root.as_deref(), Some("api"));
+        assert_eq!(parsed.submodule.as_deref(), Some("vendor"));
+        assert_eq!(parsed.pattern, "main");
+        assert!(parsed.include_ignored);
+    }
+
+    #[test]
+    fn test_json_additional_roots() {
+        let json = r#"{"query": "main", "additional_roots": ["../api", "/srv/shared"]}"#;
+        let input: Input = serde_json::from_str(json).unwrap();
+        assert_eq!(input.additional_roots, vec!["../api", "/srv/shared"]);
+    }
+
+    #[test]
+    fn test_backslash_parent_prefix() {
+        let cwd = Path::new("/home/user/project");
+        let parsed = parse_query("..\\src\\main", cwd);
+        assert_eq!(parsed.pattern, "src/main");
+        assert_eq!(parsed.output_prefix, "../");
+        assert_eq!(parsed.search_base, Path::new("/home/user"));
+
+        let parsed = parse_query("..\\..\\bar", cwd);
+        assert_eq!(parsed.pattern, "bar");
+        assert_eq!(parsed.output_prefix, "../../");
+    }
+
+    #[test]
+    fn test_backslash_current_dir_prefix() {
+        let cwd = Path::new("/home/user/project");
+        let parsed = parse_query(".\\lib", cwd);
+        assert_eq!(parsed.pattern, "lib");
+        assert_eq!(parsed.output_prefix, "");
+        assert_eq!(parsed.search_base, cwd);
+    }
+
+    #[test]
+    fn test_mixed_separators() {
+        let cwd = Path::new("/home/user/project");
+        let parsed = parse_query("../src\\..\\lib/mod.rs", cwd);
+        assert_eq!(parsed.pattern, "lib/mod.rs");
+        assert_eq!(parsed.output_prefix, "../");
+
+        let parsed = parse_query("!!sub:libfoo\\src\\lib", cwd);
+        assert_eq!(parsed.submodule.as_deref(), Some("libfoo"));
+        assert_eq!(parsed.pattern, "src/lib");
+    }
+
+    #[test]
+    fn test_escaped_space_kept() {
+        let parsed = parse_query("my\\ notes", Path::new("/home/user/project"));
+        assert_eq!(parsed.pattern, "my\\ notes");
+    }
+
+    #[test]
+    fn test_drive_letter_is_text() {
+        let cwd = Path::new("/home/user/project");
+        let parsed = parse_query("C:\\Users\\dev\\..\\src\\main.rs", cwd);
+        assert_eq!(parsed.pattern, "C:/Users/dev/../src/main.rs");
+        assert_eq!(parsed.output_prefix, "");
+        assert_eq!(parsed.search_base, cwd);
+
+        assert_eq!(parse_query("d:", cwd).pattern, "d:");
+        assert_eq!(parse_query("d:/..", cwd).output_prefix, "");
+        // Not a drive: "sub:" style words and longer names
+        assert!(!has_drive_prefix("ab:/x"));
+        assert!(!has_drive_prefix("c:x"));
+        assert!(has_drive_prefix("c:/x"));
+    }
+
+    #[test]
+    fn test_pasted_urls() {
+        let cwd = Path::new("/home/user/project");
+        let parsed = parse_query("https://github.com/org/repo/blob/main/src/x.rs#L10", cwd);
+        assert_eq!(parsed.pattern, "src/x.rs");
+        assert_eq!(parsed.search_base, cwd);
+        let pasted = parsed.pasted.unwrap();
+        assert_eq!(pasted.path, "src/x.rs");
+        assert_eq!(
+            pasted.line,
+            Some(LineAnchor {
+                start: 10,
+                end: None
+            })
+        );
+
+        let pasted = |query| parse_query(query, cwd).pasted.unwrap();
+        let gitlab =
+            pasted("https://gitlab.com/g/sub/repo/-/blob/v1.2/lib/a%20b.py?ref_type=tags#L3-L7");
+        assert_eq!(gitlab.path, "lib/a b.py");
+        assert_eq!(
+            gitlab.line,
+            Some(LineAnchor {
+                start: 3,
+                end: Some(7)
+            })
+        );
+        // No forge marker: the whole path is kept and matched by suffix
+        let raw = pasted("https://raw.githubusercontent.com/org/repo/main/src/x.rs");
+        assert_eq!(raw.path, "org/repo/main/src/x.rs");
+        assert_eq!(raw.line, None);
+
+        assert_eq!(parse_query("https://github.com/", cwd).pasted, None);
+        assert_eq!(parse_query("ab://b c", cwd).pasted, None);
+        // A drive letter is not a scheme
+        assert_eq!(parse_url("C://src/x.rs"), None);
+        assert_eq!(pasted("C://src/x.rs").path, "src/x.rs");
+        assert_eq!(parse_query("sub:libfoo main", cwd).pasted, None);
+    }
+
+    #[test]
+    fn test_pasted_absolute_paths() {
+        let cwd = Path::new("/home/user/project");
+        let parsed = parse_query("/home/ci/build/repo/src/x.rs:10:5", cwd);
+        assert_eq!(parsed.pattern, "/home/ci/build/repo/src/x.rs");
+        let pasted = parsed.pasted.unwrap();
+        assert_eq!(pasted.path, "home/ci/build/repo/src/x.rs");
+        assert_eq!(
+            pasted.line,
+            Some(LineAnchor {
+                start: 10,
+                end: None
+            })
+        );
+
+        let pasted = parse_query("C:\\build\\src\\x.rs:12:", cwd).pasted.unwrap();
+        assert_eq!(pasted.path, "build/src/x.rs");
+        assert_eq!(
+            pasted.line,
+            Some(LineAnchor {
+                start: 12,
+                end: None
+            })
+        );
+
+        // One segment or a relative path is a plain pattern
+        assert_eq!(parse_query("/etc", cwd).pasted, None);
+        assert_eq!(parse_query("src/x.rs:10", cwd).pasted, None);
+        assert_eq!(parse_query("src/x.rs:10", cwd).pattern, "src/x.rs:10");
+    }
+
+    #[test]
+    fn test_line_anchor_display() {
+        assert_eq!(
+            LineAnchor {
+                start: 4,
+                end: None
+            }
+            .to_string(),
+            "#L4"
+        );
+        assert_eq!(
+            LineAnchor {
+                start: 4,
+                end: Some(9)
+            }
+            .to_string(),
+            "#L4-9"
+        );
+    }
+
+    #[test]
+    fn test_single_bang_is_pattern() {
+        let parsed = parse_query("!foo", Path::new("/home/user/project"));
+        assert_eq!(parsed.pattern, "!foo");
+        assert!(!parsed.include_ignored);
     }
 
     #[test]
@@ -171,4 +726,123 @@ mod tests {
         let parsed = parse_query("  main.rs  ", Path::new("/home/user/project"));
         assert_eq!(parsed.pattern, "main.rs");
     }
+
+    #[test]
+    fn test_mixed_dot_segments() {
+        let cwd = Path::new("/home/user/project");
+        for query in ["./../x", ".././x", "././../x", "..//x", "../src/../x"] {
+            let parsed = parse_query(query, cwd);
+            assert_eq!(parsed.pattern, "x", "{query}");
+            assert_eq!(parsed.output_prefix, "../", "{query}");
+            assert_eq!(parsed.search_base, Path::new("/home/user"), "{query}");
+        }
+    }
+
+    #[test]
+    fn test_trailing_parent_ref() {
+        let cwd = Path::new("/home/user/project");
+        let parsed = parse_query("../foo/..", cwd);
+        assert_eq!(parsed.pattern, "");
+        assert_eq!(parsed.output_prefix, "../");
+        assert!(parsed.is_empty);
+
+        let parsed = parse_query("src/..", cwd);
+        assert!(parsed.is_empty);
+        assert_eq!(parsed.search_base, cwd);
+
+        let parsed = parse_query("./../..", cwd);
+        assert_eq!(parsed.output_prefix, "../../");
+        assert_eq!(parsed.search_base, Path::new("/home"));
+    }
+
+    #[test]
+    fn test_ascents_stop_at_root() {
+        let parsed = parse_query("../../../../../x", Path::new("/home/user/project"));
+        assert_eq!(parsed.pattern, "x");
+        assert_eq!(parsed.output_prefix, "../../../");
+        assert_eq!(parsed.search_base, Path::new("/"));
+    }
+
+    #[test]
+    fn test_other_segments_kept() {
+        let cwd = Path::new("/home/user/project");
+        assert_eq!(parse_query(".", cwd).pattern, ".");
+        assert_eq!(parse_query("src/.", cwd).pattern, "src/.");
+        assert_eq!(parse_query("src/", cwd).pattern, "src/");
+        assert_eq!(parse_query("/etc/hosts", cwd).pattern, "/etc/hosts");
+        assert_eq!(parse_query(".../x", cwd).pattern, ".../x");
+    }
+
+    mod properties {
+        use super::*;
+        use proptest::prelude::*;
+
+        /// Queries built from path-like segments, including the awkward ones
+        fn query() -> impl Strategy<Value = String> {
+            let segment = prop_oneof![
+                Just("."),
+                Just(".."),
+                Just(""),
+                Just("..."),
+                Just("src"),
+                Just("main.rs"),
+                Just(".env"),
+                Just("a b"),
+                Just("..\\x"),
+                Just("日本"),
+                Just("é"),
+            ];
+            (
+                prop::collection::vec(segment, 0..8),
+                "[ \t]{0,2}",
+                "[ \t]{0,2}",
+            )
+                .prop_map(|(segments, before, after)| {
+                    format!("{before}{}{after}", segments.join("/"))
+                })
+        }
+
+        fn cwd() -> impl Strategy<Value = PathBuf> {
+            (
+                any::<bool>(),
+                prop::collection::vec("[a-z]{1,4}|日本", 0..5),
+            )
+                .prop_map(|(absolute, segments)| {
+                    let path = segments.join("/");
+                    PathBuf::from(if absolute { format!("/{path}") } else { path })
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn prop_search_base_is_cwd_or_ancestor(query in query(), cwd in cwd()) {
+                let parsed = parse_query(&query, &cwd);
+                let ascents = parsed.output_prefix.len() / 3;
+                prop_assert_eq!(&parsed.output_prefix, &"../".repeat(ascents));
+                prop_assert_eq!(cwd.ancestors().nth(ascents), Some(parsed.search_base.as_path()));
+            }
+
+            #[test]
+            fn prop_pattern_has_no_navigation(query in query(), cwd in cwd()) {
+                let parsed = parse_query(&query, &cwd);
+                prop_assert!(!parsed.pattern.starts_with("./"), "{:?}", parsed);
+                prop_assert!(!parsed.pattern.starts_with("../"), "{:?}", parsed);
+                prop_assert_ne!(parsed.pattern.as_str(), "..");
+                prop_assert_eq!(parsed.is_empty, parsed.pattern.is_empty());
+            }
+
+            #[test]
+            fn prop_parse_is_idempotent(query in query(), cwd in cwd()) {
+                let parsed = parse_query(&query, &cwd);
+                let again = parse_query(&parsed.pattern, &parsed.search_base);
+                prop_assert_eq!(again.pattern, parsed.pattern.trim());
+                prop_assert_eq!(again.output_prefix, "");
+            }
+
+            #[test]
+            fn prop_never_panics(query in any::<String>(), cwd in any::<String>()) {
+                parse_query(&query, Path::new(&cwd));
+            }
+        }
+    }
 }
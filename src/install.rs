@@ -0,0 +1,445 @@
+//! `install` / `uninstall`: register the binary as Claude Code's file
+//! suggestion command in a settings file.
+
+use claude_search::{find_root, project_settings_path, user_settings_path};
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{Map, Value, json};
+use std::borrow::Cow;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Settings key holding the suggestion command
+const KEY: &str = "fileSuggestion";
+
+/// Appended to the settings file name for the pre-install copy
+const BACKUP_SUFFIX: &str = ".claude-search.bak";
+
+/// Which settings file to edit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    /// `~/.claude/settings.json`, for every project
+    #[default]
+    User,
+    /// `.claude/settings.json` in the project containing the current
+    /// directory (its git work tree or marker-file root), shared with the team
+    Project,
+}
+
+/// What a settings edit did.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The file was rewritten; `backup` holds the previous contents, if any
+    Written { backup: Option<PathBuf> },
+    /// The file already had the desired contents
+    Unchanged,
+}
+
+/// Run `install` or `uninstall`, returning a message for the user.
+pub fn run(scope: Scope, uninstall: bool) -> Result<String, Box<dyn Error>> {
+    let path = match scope {
+        Scope::User => user_settings_path().ok_or("cannot determine the home directory")?,
+        Scope::Project => project_settings(&std::env::current_dir()?),
+    };
+    let shown = path.display();
+
+    if uninstall {
+        return Ok(match self::uninstall(&path)? {
+            Outcome::Written { .. } => format!("Removed claude-search from {shown}"),
+            Outcome::Unchanged => format!("claude-search is not installed in {shown}"),
+        });
+    }
+
+    let exe = std::env::current_exe()?;
+    let command = exe.canonicalize().unwrap_or(exe);
+    Ok(
+        match install(&path, &shell_quote(&command.to_string_lossy()))? {
+            Outcome::Written {
+                backup: Some(backup),
+            } => format!(
+                "Installed claude-search in {shown}\nPrevious settings saved to {}",
+                backup.display()
+            ),
+            Outcome::Written { backup: None } => format!("Installed claude-search in {shown}"),
+            Outcome::Unchanged => format!("claude-search is already installed in {shown}"),
+        },
+    )
+}
+
+/// Point the `fileSuggestion` block of the settings file at `command`,
+/// keeping every other setting. The original file is backed up first;
+/// reinstalling over our own block (e.g. after the binary moved) keeps the
+/// existing backup, so it still holds the user's own settings.
+pub fn install(path: &Path, command: &str) -> io::Result<Outcome> {
+    let original = read(path)?;
+    let mut settings = parse(original.as_deref(), path)?;
+    let replacing_ours = settings.get(KEY).is_some_and(is_ours);
+    if !set_command(&mut settings, command) {
+        return Ok(Outcome::Unchanged);
+    }
+
+    let backup = backup_path(path);
+    let backup = match &original {
+        Some(_) if replacing_ours && backup.exists() => Some(backup),
+        Some(text) => {
+            fs::write(&backup, text)?;
+            Some(backup)
+        }
+        None => None,
+    };
+    write(path, &settings, original.as_deref())?;
+    Ok(Outcome::Written { backup })
+}
+
+/// Remove our `fileSuggestion` block, restoring the one saved by `install`
+/// if there was one, and delete the backup. Blocks pointing at other
+/// commands are left alone.
+pub fn uninstall(path: &Path) -> io::Result<Outcome> {
+    let Some(original) = read(path)? else {
+        return Ok(Outcome::Unchanged);
+    };
+    let mut settings = parse(Some(&original), path)?;
+
+    let backup = backup_path(path);
+    let previous = read(&backup)?
+        .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+        .and_then(|backup| backup.get(KEY).cloned());
+    if !unset_command(&mut settings, previous) {
+        return Ok(Outcome::Unchanged);
+    }
+    write(path, &settings, Some(&original))?;
+    if let Err(err) = fs::remove_file(&backup)
+        && err.kind() != io::ErrorKind::NotFound
+    {
+        return Err(err);
+    }
+    Ok(Outcome::Written { backup: None })
+}
+
+/// The shared settings file of the project containing `cwd`, so installing
+/// from a subdirectory still edits the one at the project root.
+fn project_settings(cwd: &Path) -> PathBuf {
+    project_settings_path(&find_root(cwd).unwrap_or_else(|| cwd.to_path_buf()))
+}
+
+/// Quote a program path for the shell Claude Code runs the command through,
+/// e.g. "/Users/me/My Tools/claude-search" becomes
+/// "'/Users/me/My Tools/claude-search'" (double quotes on Windows).
+fn shell_quote(path: &str) -> Cow<'_, str> {
+    let plain = |c: char| {
+        c.is_ascii_alphanumeric() || "/._-+:,@%=".contains(c) || (cfg!(windows) && c == '\\')
+    };
+    if !path.is_empty() && path.chars().all(plain) {
+        return Cow::Borrowed(path);
+    }
+    if cfg!(windows) {
+        Cow::Owned(format!("\"{path}\""))
+    } else {
+        Cow::Owned(format!("'{}'", path.replace('\'', "'\\''")))
+    }
+}
+
+/// Set the suggestion command, returning whether anything changed.
+fn set_command(settings: &mut Map<String, Value>, command: &str) -> bool {
+    let block = settings.entry(KEY).or_insert_with(|| json!({}));
+    if !block.is_object() {
+        *block = json!({});
+    }
+    let before = block.clone();
+    block["type"] = json!("command");
+    block["command"] = json!(command);
+    *block != before
+}
+
+/// Drop our suggestion command (putting `previous` back in its place),
+/// returning whether anything changed.
+fn unset_command(settings: &mut Map<String, Value>, previous: Option<Value>) -> bool {
+    if !settings.get(KEY).is_some_and(is_ours) {
+        return false;
+    }
+    match previous.filter(|block| !is_ours(block)) {
+        Some(block) => settings.insert(KEY.to_string(), block),
+        None => settings.shift_remove(KEY),
+    };
+    true
+}
+
+/// Whether a `fileSuggestion` block runs a claude-search binary.
+fn is_ours(block: &Value) -> bool {
+    block
+        .get("command")
+        .and_then(Value::as_str)
+        .map(|command| command.trim_end_matches(['\'', '"']))
+        .and_then(|command| command.rsplit(['/', '\\']).next())
+        .is_some_and(|name| name == "claude-search" || name == "claude-search.exe")
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(BACKUP_SUFFIX);
+    path.with_file_name(name)
+}
+
+/// Read a settings file, `None` if it does not exist yet.
+fn read(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Parse settings, refusing to touch anything that is not a JSON object.
+fn parse(text: Option<&str>, path: &Path) -> io::Result<Map<String, Value>> {
+    let text = text.unwrap_or_default();
+    if text.trim().is_empty() {
+        return Ok(Map::new());
+    }
+    match serde_json::from_str(text) {
+        Ok(Value::Object(settings)) => Ok(settings),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} is not a JSON object, leaving it untouched",
+                path.display()
+            ),
+        )),
+    }
+}
+
+/// Write settings with the original file's indentation and trailing newline,
+/// via a temporary file so a failed write never leaves it truncated.
+fn write(path: &Path, settings: &Map<String, Value>, original: Option<&str>) -> io::Result<()> {
+    let indent = original.and_then(detect_indent).unwrap_or("  ");
+    let mut out = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(
+        &mut out,
+        PrettyFormatter::with_indent(indent.as_bytes()),
+    );
+    settings.serialize(&mut serializer)?;
+    if original.is_none_or(|text| text.ends_with('\n')) {
+        out.push(b'\n');
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, out)?;
+    fs::rename(&tmp, path)
+}
+
+/// Leading whitespace of the first indented line.
+fn detect_indent(text: &str) -> Option<&str> {
+    text.lines()
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .find(|indent| !indent.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const COMMAND: &str = "/usr/local/bin/claude-search";
+
+    /// Copy a fixture from tests/fixtures/settings into a temp dir.
+    fn fixture(name: &str) -> (TempDir, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let source = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/settings")
+            .join(name);
+        let path = dir.path().join("settings.json");
+        fs::copy(source, &path).unwrap();
+        (dir, path)
+    }
+
+    fn keys(path: &Path) -> Vec<String> {
+        let settings: Map<String, Value> =
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        settings.keys().cloned().collect()
+    }
+
+    #[test]
+    fn test_install_preserves_other_settings() {
+        let (_dir, path) = fixture("full.json");
+        let original = fs::read_to_string(&path).unwrap();
+
+        let outcome = install(&path, COMMAND).unwrap();
+        assert_eq!(
+            outcome,
+            Outcome::Written {
+                backup: Some(backup_path(&path))
+            }
+        );
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), original);
+
+        // Same keys in the same order, 4-space indent kept, block appended
+        let text = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            keys(&path),
+            vec![
+                "$schema",
+                "model",
+                "permissions",
+                "env",
+                "statusLine",
+                "fileSuggestion"
+            ]
+        );
+        assert!(text.starts_with("{\n    \"$schema\""), "{text}");
+        assert!(text.ends_with("}\n"));
+        let settings: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(settings["permissions"]["deny"][0], "Read(./.env)");
+        assert_eq!(
+            settings[KEY],
+            json!({"type": "command", "command": COMMAND})
+        );
+
+        // Installing again changes nothing
+        assert_eq!(install(&path, COMMAND).unwrap(), Outcome::Unchanged);
+    }
+
+    #[test]
+    fn test_uninstall_removes_block() {
+        let (_dir, path) = fixture("full.json");
+        let original = fs::read_to_string(&path).unwrap();
+        install(&path, COMMAND).unwrap();
+
+        assert_eq!(uninstall(&path).unwrap(), Outcome::Written { backup: None });
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(uninstall(&path).unwrap(), Outcome::Unchanged);
+    }
+
+    #[test]
+    fn test_uninstall_restores_previous_command() {
+        let (_dir, path) = fixture("shell_script.json");
+        let original = fs::read_to_string(&path).unwrap();
+
+        install(&path, COMMAND).unwrap();
+        // The block keeps its position
+        assert_eq!(keys(&path), vec!["fileSuggestion", "cleanupPeriodDays"]);
+
+        uninstall(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+    }
+
+    #[test]
+    fn test_reinstall_keeps_original_backup() {
+        let (_dir, path) = fixture("shell_script.json");
+        let original = fs::read_to_string(&path).unwrap();
+
+        install(&path, COMMAND).unwrap();
+        // The binary moved: the backup still holds the user's own block
+        let outcome = install(&path, "/opt/bin/claude-search").unwrap();
+        assert_eq!(
+            outcome,
+            Outcome::Written {
+                backup: Some(backup_path(&path))
+            }
+        );
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), original);
+
+        uninstall(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[test]
+    fn test_uninstall_leaves_other_commands() {
+        let (_dir, path) = fixture("shell_script.json");
+        let original = fs::read_to_string(&path).unwrap();
+        assert_eq!(uninstall(&path).unwrap(), Outcome::Unchanged);
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+    }
+
+    #[test]
+    fn test_install_compact_file_without_newline() {
+        let (_dir, path) = fixture("compact.json");
+        install(&path, COMMAND).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(!text.ends_with('\n'));
+        assert_eq!(keys(&path), vec!["theme", "fileSuggestion"]);
+    }
+
+    #[test]
+    fn test_install_creates_missing_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join(".claude/settings.json");
+
+        assert_eq!(
+            install(&path, COMMAND).unwrap(),
+            Outcome::Written { backup: None }
+        );
+        let settings: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(settings[KEY]["command"], COMMAND);
+    }
+
+    #[test]
+    fn test_invalid_settings_untouched() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "{ \"model\": ").unwrap();
+
+        assert!(install(&path, COMMAND).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ \"model\": ");
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[test]
+    fn test_project_settings_at_root() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::create_dir_all(dir.path().join("src/net")).unwrap();
+        assert_eq!(
+            project_settings(&dir.path().join("src/net")),
+            dir.path().join(".claude/settings.json")
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote(COMMAND), COMMAND);
+        if cfg!(windows) {
+            assert_eq!(
+                shell_quote("C:\\My Tools\\claude-search.exe"),
+                "\"C:\\My Tools\\claude-search.exe\""
+            );
+        } else {
+            assert_eq!(
+                shell_quote("/Users/me/My Tools/claude-search"),
+                "'/Users/me/My Tools/claude-search'"
+            );
+            assert_eq!(
+                shell_quote("/opt/it's/claude-search"),
+                "'/opt/it'\\''s/claude-search'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_install_path_with_space() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("settings.json");
+        let command = shell_quote("/Users/me/My Tools/claude-search").into_owned();
+
+        install(&path, &command).unwrap();
+        let settings: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(settings[KEY]["command"], command.as_str());
+        assert_ne!(command, "/Users/me/My Tools/claude-search");
+        // Still recognised as ours when quoted
+        assert_eq!(uninstall(&path).unwrap(), Outcome::Written { backup: None });
+    }
+
+    #[test]
+    fn test_is_ours() {
+        assert!(is_ours(&json!({"command": "/usr/local/bin/claude-search"})));
+        assert!(is_ours(&json!({"command": "C:\\tools\\claude-search.exe"})));
+        assert!(!is_ours(
+            &json!({"command": "~/.claude/file-suggestion.sh"})
+        ));
+        assert!(!is_ours(&json!("claude-search")));
+    }
+}
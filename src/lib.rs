@@ -0,0 +1,49 @@
+//! Fast fuzzy file suggestions, as used by the `claude-search` binary.
+//!
+//! The pipeline is [`parse_query`] -> [`walk_entries`] -> [`FuzzyMatcher`],
+//! wrapped by the [`Search`] builder which returns typed [`SearchResult`]s:
+//!
+//! ```no_run
+//! use claude_search::{Search, SymlinkPolicy};
+//!
+//! let results = Search::new("/path/to/project")
+//!     .query("src/main")
+//!     .symlinks(SymlinkPolicy::WithinRoot)
+//!     .run();
+//! for result in &results {
+//!     println!("{} {:?} {}", result.path, result.kind, result.score);
+//! }
+//! ```
+
+mod config;
+mod doctor;
+mod imports;
+mod input;
+mod matcher;
+mod output;
+mod project;
+mod repo;
+mod roots;
+mod search;
+mod sensitive;
+mod settings;
+mod submodules;
+mod unicode;
+mod walker;
+
+pub use config::{Config, state_dir};
+pub use doctor::{Diagnosis, Reason};
+pub use input::{Input, LineAnchor, ParsedQuery, PastedPath, parse_query};
+pub use matcher::{FuzzyMatcher, MIN_MATCHES, Scored, match_typos};
+pub use output::{OutputFormat, quote_path, write_results};
+pub use project::{SearchRoot, find_root};
+pub use search::{DEFAULT_LIMIT, Search, SearchResult, SearchStats};
+pub use sensitive::{DEFAULT_SENSITIVE_PATTERNS, SensitiveFiles, SensitivePolicy};
+pub use settings::{
+    DenyRule, DenyRules, local_settings_path, project_settings_path, user_settings_path,
+};
+pub use submodules::SubmodulePolicy;
+pub use walker::{
+    DEEP_CONFIG, Entry, EntryKind, Limits, SHALLOW_CONFIG, SymlinkPolicy, WalkConfig, walk_entries,
+    walk_files,
+};
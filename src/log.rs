@@ -0,0 +1,182 @@
+//! Opt-in diagnostic log: one JSON record per request, appended to
+//! `<state dir>/claude-search.log`. Logging never fails a request and never
+//! writes to stdout or stderr.
+
+use claude_search::{Config, SearchStats, state_dir};
+use serde_json::{Map, Value, json};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// `1` enables the log, `0` disables it, overriding the config file
+const LOG_ENV: &str = "CLAUDE_SEARCH_LOG";
+
+const LOG_FILE: &str = "claude-search.log";
+
+/// Size at which the log is rotated to `claude-search.log.1`
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Longest raw input stored in a record
+const MAX_INPUT_CHARS: usize = 4096;
+
+/// The log file, if logging is enabled.
+pub fn log_path(config: &Config) -> Option<PathBuf> {
+    let enabled = match std::env::var(LOG_ENV).as_deref() {
+        Ok("1" | "true") => true,
+        Ok("0" | "false") => false,
+        _ => config.log,
+    };
+    if !enabled {
+        return None;
+    }
+    Some(state_dir()?.join(LOG_FILE))
+}
+
+/// A log record being filled in while a request runs.
+pub struct Record {
+    started: Instant,
+    fields: Map<String, Value>,
+}
+
+impl Record {
+    pub fn new(mode: &str) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+        let mut fields = Map::new();
+        fields.insert("ts".to_string(), json!(timestamp));
+        fields.insert("mode".to_string(), json!(mode));
+        fields.insert("version".to_string(), json!(env!("CARGO_PKG_VERSION")));
+        Self {
+            started: Instant::now(),
+            fields,
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<Value>) {
+        self.fields.insert(key.to_string(), value.into());
+    }
+
+    /// Store the request: as JSON if it parses, otherwise the (truncated) text.
+    pub fn input(&mut self, raw: &str) {
+        let value = serde_json::from_str(raw)
+            .unwrap_or_else(|_| Value::String(raw.chars().take(MAX_INPUT_CHARS).collect()));
+        self.set("input", value);
+    }
+
+    pub fn stats(&mut self, stats: &SearchStats) {
+        if let Some(base) = &stats.search_base {
+            self.set("search_base", base.to_string_lossy());
+        }
+        if !stats.additional_roots.is_empty() {
+            let roots: Vec<_> = stats
+                .additional_roots
+                .iter()
+                .map(|root| root.to_string_lossy())
+                .collect();
+            self.set("additional_roots", roots);
+        }
+        self.set("walk_ms", millis(stats.walk_time));
+        self.set("match_ms", millis(stats.match_time));
+        self.set("candidates", stats.candidates);
+        self.set("ignored_candidates", stats.ignored_candidates);
+        self.set("sensitive_candidates", stats.sensitive_candidates);
+        self.set("walk_errors", stats.walk_errors);
+        self.set("walk_truncated", stats.walk_truncated);
+    }
+
+    /// Append the record to `path`, rotating the log when it gets large.
+    /// Failures are ignored: the log must never break a request.
+    pub fn finish(mut self, path: &Path) {
+        self.set("total_ms", millis(self.started.elapsed()));
+        let _ = append(path, &Value::Object(self.fields));
+    }
+}
+
+fn millis(duration: std::time::Duration) -> f64 {
+    (duration.as_secs_f64() * 1000.0 * 1000.0).round() / 1000.0
+}
+
+fn append(path: &Path, record: &Value) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    if fs::metadata(path).is_ok_and(|m| m.len() >= MAX_LOG_BYTES) {
+        let mut rotated = path.as_os_str().to_os_string();
+        rotated.push(".1");
+        fs::rename(path, rotated)?;
+    }
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+    // A single write per record keeps concurrent appends from interleaving
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(&line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn records(path: &Path) -> Vec<Value> {
+        fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_record_fields() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("state/claude-search.log");
+
+        let mut record = Record::new("hook");
+        record.input(r#"{"query": "main"}"#);
+        record.stats(&SearchStats::default());
+        record.set("results", 3);
+        record.finish(&path);
+
+        let mut record = Record::new("hook");
+        record.input("not json");
+        record.set("error", "expected value at line 1 column 1");
+        record.finish(&path);
+
+        let records = records(&path);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["mode"], "hook");
+        assert_eq!(records[0]["input"]["query"], "main");
+        assert_eq!(records[0]["candidates"], 0);
+        assert_eq!(records[0]["results"], 3);
+        assert!(records[0]["total_ms"].is_number());
+        assert_eq!(records[1]["input"], "not json");
+        assert!(records[1]["error"].is_string());
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("claude-search.log");
+        fs::write(&path, vec![b'x'; MAX_LOG_BYTES as usize]).unwrap();
+
+        Record::new("hook").finish(&path);
+
+        assert_eq!(records(&path).len(), 1);
+        let rotated = dir.path().join("claude-search.log.1");
+        assert_eq!(fs::metadata(rotated).unwrap().len(), MAX_LOG_BYTES);
+    }
+
+    #[test]
+    fn test_input_truncated() {
+        let mut record = Record::new("hook");
+        record.input(&"x".repeat(MAX_INPUT_CHARS * 2));
+        assert_eq!(
+            record.fields["input"].as_str().unwrap().len(),
+            MAX_INPUT_CHARS
+        );
+    }
+}
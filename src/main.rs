@@ -1,64 +1,155 @@
-mod input;
-mod matcher;
-mod walker;
-
+use claude_search::{Config, Input, Search, write_results};
+use cli::Command;
 use mimalloc::MiMalloc;
 use std::io::{self, BufWriter, Read, Write};
-use std::path::PathBuf;
+
+mod bench;
+mod cli;
+mod install;
+mod log;
+mod mcp;
 
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
 fn main() {
-    if run().is_err() {
-        // Silent failure - exit 0 per requirements
+    let command = match cli::parse(std::env::args_os().skip(1)) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("error: {message}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    let result = match command {
+        Command::Hook => run(),
+        Command::Mcp => mcp::serve(io::stdin().lock(), io::stdout().lock()).map_err(Into::into),
+        Command::Find(input) => {
+            report(search(input, &Config::load(), None).map(|()| String::new()))
+        }
+        Command::Doctor(input, path) => report(doctor(input, &path).map(|()| String::new())),
+        Command::Install(scope) => report(install::run(scope, false)),
+        Command::Uninstall(scope) => report(install::run(scope, true)),
+        Command::Bench(options) => report(bench::run(&options).map(|()| String::new())),
+        Command::Help => {
+            print!("{}", cli::USAGE);
+            Ok(())
+        }
+        Command::Version => {
+            println!("claude-search {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+    };
+    if result.is_err() {
+        // Silent failure - exit 0 per requirements, for the stdin hook only
         std::process::exit(0);
     }
 }
 
+/// Print the outcome of an interactive command; failures exit non-zero.
+fn report(
+    result: Result<String, Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match result {
+        Ok(message) => {
+            if !message.is_empty() {
+                println!("{message}");
+            }
+            Ok(())
+        }
+        // The reader went away, e.g. `claude-search find main | head -1`
+        Err(err)
+            if err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Print why `path` is or is not suggested.
+fn doctor(mut input: Input, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // Without a query, test what a user would most likely type: the file name
+    let query = input.query.take().unwrap_or_else(|| {
+        let name = path.trim_end_matches('/').rsplit('/').next();
+        name.unwrap_or(path).to_string()
+    });
+    input.query = Some(query.clone());
+    let diagnosis = Search::from_input(input, &Config::load()).diagnose(path);
+
+    let mut out = io::stdout().lock();
+    writeln!(out, "{}", diagnosis.path)?;
+    writeln!(out, "  query: {query}")?;
+    match (diagnosis.rank, diagnosis.score) {
+        (Some(rank), Some(score)) => writeln!(out, "  rank:  #{rank} (score {score})")?,
+        _ => writeln!(out, "  rank:  -")?,
+    }
+    if diagnosis.is_suggested() {
+        writeln!(out, "  suggested")?;
+    } else {
+        writeln!(out, "  not suggested:")?;
+        for reason in &diagnosis.reasons {
+            writeln!(out, "  - {reason}")?;
+        }
+    }
+    Ok(())
+}
+
 fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load();
+    let Some(log_path) = log::log_path(&config) else {
+        return hook(&config, None);
+    };
+
+    // Record what happened, since errors are otherwise invisible here
+    let mut record = log::Record::new("hook");
+    let result = hook(&config, Some(&mut record));
+    if let Err(err) = &result {
+        record.set("error", err.to_string());
+    }
+    record.finish(&log_path);
+    result
+}
+
+fn hook(
+    config: &Config,
+    mut record: Option<&mut log::Record>,
+) -> Result<(), Box<dyn std::error::Error>> {
     // Read stdin
     let mut buffer = String::new();
     io::stdin().read_to_string(&mut buffer)?;
+    if let Some(record) = record.as_deref_mut() {
+        record.input(&buffer);
+    }
 
     // Parse JSON input
-    let input: input::Input = serde_json::from_str(&buffer)?;
-
-    // Get cwd, default to current directory
-    let cwd = input
-        .cwd
-        .map(PathBuf::from)
-        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+    let input: Input = serde_json::from_str(&buffer)?;
+    search(input, config, record)
+}
 
-    // Parse query and extract prefix/pattern
-    let query = input.query.as_deref().unwrap_or("");
-    let parsed = input::parse_query(query, &cwd);
+fn search(
+    input: Input,
+    config: &Config,
+    record: Option<&mut log::Record>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let format = input.format;
 
-    // Verify search base exists
-    if !parsed.search_base.exists() {
-        return Ok(()); // Silent failure
+    // Search, with the user config as defaults
+    let (results, stats) = Search::from_input(input, config).run_with_stats();
+    if let Some(record) = record {
+        record.stats(&stats);
+        record.set("results", results.len());
     }
 
-    // Configure walk depth based on whether we have a pattern
-    let walk_config = if parsed.is_empty {
-        &walker::SHALLOW_CONFIG
-    } else {
-        &walker::DEEP_CONFIG
-    };
-
-    // Walk files
-    let paths = walker::walk_files(&parsed.search_base, walk_config);
-
-    // Match and rank
-    let mut fuzzy_matcher = matcher::FuzzyMatcher::new();
-    let results = fuzzy_matcher.match_paths(paths, &parsed.pattern, 50);
-
-    // Output results with prefix
+    // Output results
     let stdout = io::stdout();
     let mut writer = BufWriter::new(stdout.lock());
-    for path in results {
-        writeln!(writer, "{}{}", parsed.output_prefix, path)?;
-    }
+    write_results(&mut writer, format, &results)?;
     writer.flush()?;
 
     Ok(())
@@ -1,12 +1,34 @@
+use crate::unicode;
 use nucleo_matcher::{
     Config, Matcher, Utf32Str,
     pattern::{CaseMatching, Normalization, Pattern},
 };
+use std::borrow::Cow;
+
+/// With fewer fuzzy matches than this, near misses by edit distance are
+/// suggested below them ("cofnig" finds "config.rs").
+pub const MIN_MATCHES: usize = 3;
 
 pub struct FuzzyMatcher {
     matcher: Matcher,
 }
 
+/// A matched item with its score and the matched character positions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scored<T> {
+    pub item: T,
+    /// Nucleo score (0 when there is no pattern)
+    pub score: u32,
+    /// Sorted char indices of the matched characters
+    pub positions: Vec<u32>,
+}
+
+impl Default for FuzzyMatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FuzzyMatcher {
     pub fn new() -> Self {
         // Config optimized for file path matching
@@ -16,36 +38,213 @@ impl FuzzyMatcher {
     }
 
     /// Match paths against pattern, return top N sorted by score (descending).
+    /// When fewer than [`MIN_MATCHES`] paths match, near misses follow.
     pub fn match_paths(&mut self, paths: Vec<String>, pattern: &str, limit: usize) -> Vec<String> {
+        let (mut matched, missed) = self.match_split(paths, pattern, limit, String::as_str);
+        if matched.len() < MIN_MATCHES {
+            let remaining = limit - matched.len();
+            matched.extend(match_typos(missed, pattern, remaining, String::as_str));
+        }
+        matched.into_iter().map(|scored| scored.item).collect()
+    }
+
+    /// Match arbitrary items by the path returned from `key`, returning the
+    /// top N with scores and matched positions, sorted by score (descending).
+    pub fn match_items<T>(
+        &mut self,
+        items: Vec<T>,
+        pattern: &str,
+        limit: usize,
+        key: impl Fn(&T) -> &str,
+    ) -> Vec<Scored<T>> {
+        self.match_split(items, pattern, limit, key).0
+    }
+
+    /// Like [`match_items`](Self::match_items), also handing back the items
+    /// that did not match at all, for [`match_typos`].
+    pub fn match_split<T>(
+        &mut self,
+        items: Vec<T>,
+        pattern: &str,
+        limit: usize,
+        key: impl Fn(&T) -> &str,
+    ) -> (Vec<Scored<T>>, Vec<T>) {
         if pattern.is_empty() {
-            // No pattern - return first N paths as-is
-            return paths.into_iter().take(limit).collect();
+            // No pattern - return first N items as-is
+            let scored = items
+                .into_iter()
+                .take(limit)
+                .map(|item| Scored {
+                    item,
+                    score: 0,
+                    positions: Vec::new(),
+                })
+                .collect();
+            return (scored, Vec::new());
         }
 
-        // Parse pattern with smart case matching
-        let pat = Pattern::parse(pattern, CaseMatching::Smart, Normalization::Smart);
+        // Parse pattern with smart case matching. Both sides are composed
+        // first (nucleo only folds precomposed letters), so "café" finds a
+        // decomposed "cafe\u{301}" and the other way around
+        let pattern = unicode::compose(pattern).map_or(Cow::Borrowed(pattern), |(p, _)| p.into());
+        let pat = Pattern::parse(&pattern, CaseMatching::Smart, Normalization::Smart);
+
+        // Score each item
+        let mut buf = Vec::new();
+        let mut scored: Vec<(T, u32)> = Vec::new();
+        let mut missed = Vec::new();
+        for item in items {
+            let path = key(&item);
+            let composed = unicode::compose(path);
+            let path = composed.as_ref().map_or(path, |(c, _)| c.as_str());
+            let haystack = Utf32Str::new(path, &mut buf);
+            match pat.score(haystack, &mut self.matcher) {
+                Some(score) => scored.push((item, score)),
+                None => missed.push(item),
+            }
+        }
 
-        // Score each path
-        let mut scored: Vec<(String, u32)> = paths
+        // Sort by score descending; ties go to the shorter, then the
+        // alphabetically first path so rankings do not depend on walk order
+        scored.sort_by(|(a, a_score), (b, b_score)| {
+            let (a, b) = (key(a), key(b));
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.len().cmp(&b.len()))
+                .then_with(|| a.cmp(b))
+        });
+
+        // Take top N, computing positions only for those
+        let scored = scored
             .into_iter()
-            .filter_map(|path| {
-                let mut buf = Vec::new();
-                let haystack = Utf32Str::new(&path, &mut buf);
-                pat.score(haystack, &mut self.matcher)
-                    .map(|score| (path, score))
+            .take(limit)
+            .map(|(item, score)| {
+                let mut positions = Vec::new();
+                let path = key(&item);
+                let composed = unicode::compose(path);
+                let haystack = match &composed {
+                    Some((c, _)) => Utf32Str::new(c, &mut buf),
+                    None => Utf32Str::new(path, &mut buf),
+                };
+                pat.indices(haystack, &mut self.matcher, &mut positions);
+                // Positions refer to the original, possibly decomposed path
+                if let Some((_, starts)) = &composed {
+                    for pos in &mut positions {
+                        *pos = starts[*pos as usize];
+                    }
+                }
+                positions.sort_unstable();
+                positions.dedup();
+                Scored {
+                    item,
+                    score,
+                    positions,
+                }
             })
             .collect();
+        (scored, missed)
+    }
+}
 
-        // Sort by score descending
-        scored.sort_by(|a, b| b.1.cmp(&a.1));
+/// "Did you mean" candidates for a pattern that fuzzy matching cannot find
+/// because of a typo: items with a file name or directory (with or without
+/// its extension) within a small edit distance of the pattern's last
+/// segment, counting a swap of neighbours as one edit. Returns the top N,
+/// closest first, then file names before directories, then shorter paths;
+/// scores are 0 and positions cover the near-miss segment.
+pub fn match_typos<T>(
+    items: Vec<T>,
+    pattern: &str,
+    limit: usize,
+    key: impl Fn(&T) -> &str,
+) -> Vec<Scored<T>> {
+    let target = pattern
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    let target: Vec<char> = target.chars().collect();
+    let max_edits = match target.len() {
+        0..4 => return Vec::new(),
+        4..8 => 1,
+        _ => 2,
+    };
+    if pattern.contains(char::is_whitespace) {
+        return Vec::new();
+    }
 
-        // Take top N
-        scored
-            .into_iter()
-            .take(limit)
-            .map(|(path, _)| path)
-            .collect()
+    // (distance, whether a directory matched, item, matched char range)
+    let mut near: Vec<(usize, bool, T, (u32, u32))> = items
+        .into_iter()
+        .filter_map(|item| {
+            let path = key(&item);
+            let segments = path.split('/').collect::<Vec<_>>();
+            let mut best: Option<(usize, bool, (u32, u32))> = None;
+            let mut offset = 0;
+            for (i, segment) in segments.iter().enumerate() {
+                let start = offset as u32;
+                offset += segment.chars().count() + 1;
+                let stem = segment.split_once('.').map_or("", |(stem, _)| stem);
+                for candidate in [*segment, stem] {
+                    let chars: Vec<char> = candidate.to_lowercase().chars().collect();
+                    if chars.is_empty() {
+                        continue;
+                    }
+                    let Some(distance) = edit_distance(&target, &chars, max_edits) else {
+                        continue;
+                    };
+                    let is_dir = i + 1 < segments.len();
+                    let range = (start, start + candidate.chars().count() as u32);
+                    if best.is_none_or(|(d, dir, _)| (distance, is_dir) < (d, dir)) {
+                        best = Some((distance, is_dir, range));
+                    }
+                }
+            }
+            best.map(|(distance, is_dir, range)| (distance, is_dir, item, range))
+        })
+        .collect();
+
+    near.sort_by(|(a_dist, a_dir, a, _), (b_dist, b_dir, b, _)| {
+        let (a, b) = (key(a), key(b));
+        (a_dist, a_dir, a.len(), a).cmp(&(b_dist, b_dir, b.len(), b))
+    });
+    near.into_iter()
+        .take(limit)
+        .map(|(_, _, item, (start, end))| Scored {
+            item,
+            score: 0,
+            positions: (start..end).collect(),
+        })
+        .collect()
+}
+
+/// Optimal string alignment distance between `a` and `b` (insertions,
+/// deletions, substitutions and swaps of adjacent characters), or `None`
+/// if it exceeds `max`.
+fn edit_distance(a: &[char], b: &[char], max: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let width = b.len() + 1;
+    let mut rows = vec![0; (a.len() + 1) * width];
+    for (j, cell) in rows[..width].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        rows[i * width] = i;
+        for j in 1..width {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[(i - 1) * width + j] + 1)
+                .min(rows[i * width + j - 1] + 1)
+                .min(rows[(i - 1) * width + j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[(i - 2) * width + j - 2] + 1);
+            }
+            rows[i * width + j] = best;
+        }
     }
+    let distance = rows[a.len() * width + b.len()];
+    (distance <= max).then_some(distance)
 }
 
 #[cfg(test)]
@@ -135,6 +334,140 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_typo_fallback() {
+        let mut matcher = FuzzyMatcher::new();
+        let paths = vec![
+            "src/config.rs".to_string(),
+            "src/confirm.rs".to_string(),
+            "docs/config/readme.md".to_string(),
+            "main.rs".to_string(),
+        ];
+
+        // A swap and an extra letter: file names first, then directories
+        let results = matcher.match_paths(paths.clone(), "cofnig", 10);
+        assert_eq!(results, vec!["src/config.rs", "docs/config/readme.md"]);
+        let results = matcher.match_paths(paths.clone(), "confiig.rs", 10);
+        assert_eq!(results[0], "src/config.rs");
+
+        // Short patterns are too ambiguous for near misses
+        assert!(matcher.match_paths(paths, "mna", 10).is_empty());
+    }
+
+    #[test]
+    fn test_typos_rank_below_matches() {
+        let mut matcher = FuzzyMatcher::new();
+        let paths = vec!["config.rs".to_string(), "cofnig_notes.md".to_string()];
+        let results = matcher.match_paths(paths, "cofnig", 10);
+        assert_eq!(results, vec!["cofnig_notes.md", "config.rs"]);
+    }
+
+    #[test]
+    fn test_typo_positions() {
+        let results = match_typos(vec!["src/config.rs"], "cofnig", 10, |p| p);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].score, 0);
+        assert_eq!(results[0].positions, vec![4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        assert_eq!(
+            edit_distance(&chars("cofnig"), &chars("config"), 2),
+            Some(1)
+        );
+        assert_eq!(
+            edit_distance(&chars("config"), &chars("config"), 2),
+            Some(0)
+        );
+        assert_eq!(edit_distance(&chars("cnfg"), &chars("config"), 2), Some(2));
+        assert_eq!(edit_distance(&chars("cfg"), &chars("config"), 2), None);
+        assert_eq!(edit_distance(&chars("abcd"), &chars("wxyz"), 2), None);
+    }
+
+    #[test]
+    fn test_match_items_scores_and_positions() {
+        let mut matcher = FuzzyMatcher::new();
+        let items = vec![("src/main.rs", 1), ("docs/readme.md", 2)];
+
+        let results = matcher.match_items(items, "main", 10, |item| item.0);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].item, ("src/main.rs", 1));
+        assert!(results[0].score > 0);
+        assert_eq!(results[0].positions, vec![4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn test_match_items_empty_pattern_unscored() {
+        let mut matcher = FuzzyMatcher::new();
+        let results = matcher.match_items(vec!["a", "b"], "", 10, |item| item);
+        assert_eq!(results.len(), 2);
+        assert!(
+            results
+                .iter()
+                .all(|r| r.score == 0 && r.positions.is_empty())
+        );
+    }
+
+    #[test]
+    fn test_ties_broken_by_length_then_name() {
+        let mut matcher = FuzzyMatcher::new();
+        let paths = vec![
+            "b/util.rs".to_string(),
+            "lib/util.rs".to_string(),
+            "a/util.rs".to_string(),
+        ];
+
+        let results = matcher.match_paths(paths, "util.rs", 10);
+        assert_eq!(results, vec!["a/util.rs", "b/util.rs", "lib/util.rs"]);
+    }
+
+    #[test]
+    fn test_composed_query_matches_decomposed_path() {
+        let mut matcher = FuzzyMatcher::new();
+        let items = vec!["docs/cafe\u{301}.md", "docs/cafeteria.md"];
+
+        let results = matcher.match_items(items, "caf\u{e9}.md", 10, |item| item);
+        assert_eq!(results[0].item, "docs/cafe\u{301}.md");
+        // Positions index the decomposed path; the mark itself is not one
+        assert_eq!(results[0].positions, vec![5, 6, 7, 8, 10, 11, 12]);
+    }
+
+    #[test]
+    fn test_decomposed_query_matches_composed_path() {
+        let mut matcher = FuzzyMatcher::new();
+        let paths = vec!["notes/r\u{e9}sum\u{e9}.txt".to_string()];
+
+        let results = matcher.match_paths(paths, "re\u{301}sume\u{301}", 10);
+        assert_eq!(results, vec!["notes/r\u{e9}sum\u{e9}.txt"]);
+    }
+
+    #[test]
+    fn test_cjk_paths() {
+        let mut matcher = FuzzyMatcher::new();
+        // "ガイド" stored decomposed, as on macOS, next to a Han-only path
+        let items = vec![
+            "docs/\u{30ab}\u{3099}\u{30a4}\u{30c8}\u{3099}.md",
+            "\u{6587}\u{66f8}/\u{8aac}\u{660e}.md",
+        ];
+
+        let results = matcher.match_items(items.clone(), "\u{30ac}\u{30a4}\u{30c9}", 10, |i| i);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].item, items[0]);
+        assert_eq!(results[0].positions, vec![5, 7, 8]);
+
+        let results = matcher.match_items(items.clone(), "\u{8aac}\u{660e}", 10, |i| i);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].item, items[1]);
+        assert_eq!(results[0].positions, vec![3, 4]);
+
+        // Hangul typed as a syllable matches jamo in the path
+        let jamo = vec!["\u{1112}\u{1161}\u{11ab}\u{1100}\u{1173}\u{11af}.txt"];
+        let results = matcher.match_items(jamo, "\u{d55c}\u{ae00}", 10, |i| i);
+        assert_eq!(results[0].positions, vec![0, 3]);
+    }
+
     #[test]
     fn test_partial_path_match() {
         let mut matcher = FuzzyMatcher::new();
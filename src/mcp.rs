@@ -0,0 +1,266 @@
+//! Model Context Protocol server over stdio (newline-delimited JSON-RPC 2.0).
+
+use claude_search::{Config, EntryKind, Search, SearchResult, quote_path};
+use serde_json::{Value, json};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Protocol revisions we can speak, newest first.
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Serve requests from `reader` until it is closed.
+pub fn serve<R: BufRead, W: Write>(reader: R, mut writer: W) -> io::Result<()> {
+    let config = Config::load();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_message(&line, &config) {
+            serde_json::to_writer(&mut writer, &response)?;
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Handle one message, returning the response (none for notifications).
+fn handle_message(line: &str, config: &Config) -> Option<Value> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(err) => return Some(error_response(Value::Null, PARSE_ERROR, &err.to_string())),
+    };
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        let id = message.get("id").cloned().unwrap_or(Value::Null);
+        return Some(error_response(id, INVALID_REQUEST, "Missing method"));
+    };
+    // Notifications (e.g. notifications/initialized) get no response
+    let id = message.get("id").cloned()?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => Ok(initialize(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+        "tools/call" => call_tool(&params, config),
+        _ => Err((METHOD_NOT_FOUND, format!("Unknown method: {method}"))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn initialize(params: &Value) -> Value {
+    // Echo the client's version if we support it, otherwise offer our newest
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "find_files",
+            "description": "Fuzzy-find files and directories by path. Respects .gitignore. \
+                Supports the same query syntax as @-mentions: \"../\" prefixes to search \
+                parent directories and a leading \"!!\" to include ignored files.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Fuzzy path query, e.g. \"src/main\"" },
+                    "cwd": { "type": "string", "description": "Directory to search from (defaults to the server's working directory)" },
+                    "limit": { "type": "integer", "minimum": 1, "description": "Maximum number of results (default 50)" },
+                },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "list_dir",
+            "description": "List the entries of a directory, skipping ignored files. Directories end with \"/\".",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory to list" },
+                },
+                "required": ["path"],
+            },
+        },
+    ])
+}
+
+fn call_tool(params: &Value, config: &Config) -> Result<Value, (i64, String)> {
+    let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+    let args = params.get("arguments").cloned().unwrap_or(json!({}));
+    let string_arg = |key: &str| args.get(key).and_then(Value::as_str);
+
+    match name {
+        "find_files" => {
+            let query = string_arg("query")
+                .ok_or((INVALID_PARAMS, "find_files requires a query".to_string()))?;
+            let limit = args
+                .get("limit")
+                .and_then(Value::as_u64)
+                .map_or(claude_search::DEFAULT_LIMIT, |n| n as usize);
+            let cwd = resolve(string_arg("cwd").unwrap_or("."));
+            let results = Search::new(cwd)
+                .config(config)
+                .query(query)
+                .limit(limit)
+                .run();
+            Ok(find_files_result(&results))
+        }
+        "list_dir" => {
+            let path = string_arg("path")
+                .ok_or((INVALID_PARAMS, "list_dir requires a path".to_string()))?;
+            Ok(list_dir_result(&resolve(path), config))
+        }
+        _ => Err((INVALID_PARAMS, format!("Unknown tool: {name}"))),
+    }
+}
+
+/// Resolve a tool path argument against the server's working directory.
+fn resolve(path: &str) -> PathBuf {
+    std::env::current_dir().unwrap_or_default().join(path)
+}
+
+fn find_files_result(results: &[SearchResult]) -> Value {
+    let text = if results.is_empty() {
+        "No matching files".to_string()
+    } else {
+        let paths: Vec<_> = results.iter().map(quote_path).collect();
+        paths_text(paths.iter().map(|p| p.as_ref()))
+    };
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "structuredContent": { "results": results },
+    })
+}
+
+fn list_dir_result(dir: &Path, config: &Config) -> Value {
+    if !dir.is_dir() {
+        return json!({
+            "content": [{ "type": "text", "text": format!("Not a directory: {}", dir.display()) }],
+            "isError": true,
+        });
+    }
+    // Listed through Search so sensitive files, deny rules and limits apply
+    // as they do for find_files
+    let results = Search::new(dir)
+        .config(config)
+        .additional_roots(Vec::<PathBuf>::new())
+        .max_depth(1)
+        .limit(usize::MAX)
+        .run();
+    let mut entries: Vec<String> = results
+        .iter()
+        .map(|result| match result.kind {
+            EntryKind::Dir => format!("{}/", quote_path(result)),
+            _ => quote_path(result).into_owned(),
+        })
+        .collect();
+    entries.sort();
+    json!({
+        "content": [{ "type": "text", "text": paths_text(entries.iter().map(String::as_str)) }],
+    })
+}
+
+fn paths_text<'a>(paths: impl Iterator<Item = &'a str>) -> String {
+    paths.collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(line: &str) -> Value {
+        handle_message(line, &Config::default()).unwrap()
+    }
+
+    #[test]
+    fn test_initialize_negotiates_version() {
+        let response = request(
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05"}}"#,
+        );
+        assert_eq!(response["id"], 1);
+        assert_eq!(response["result"]["protocolVersion"], "2024-11-05");
+        assert!(response["result"]["capabilities"]["tools"].is_object());
+
+        let response = request(
+            r#"{"jsonrpc":"2.0","id":2,"method":"initialize","params":{"protocolVersion":"1999-01-01"}}"#,
+        );
+        assert_eq!(response["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+
+    #[test]
+    fn test_notifications_get_no_response() {
+        let line = r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#;
+        assert!(handle_message(line, &Config::default()).is_none());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(request("{not json")["error"]["code"], PARSE_ERROR);
+        assert_eq!(
+            request(r#"{"jsonrpc":"2.0","id":3,"method":"resources/list"}"#)["error"]["code"],
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            request(r#"{"jsonrpc":"2.0","id":4}"#)["error"]["code"],
+            INVALID_REQUEST
+        );
+        let response = request(
+            r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"find_files","arguments":{}}}"#,
+        );
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_list_dir_hides_sensitive_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for name in [".env", "id_rsa", "main.rs"] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+
+        let result = list_dir_result(dir.path(), &Config::default());
+        assert_eq!(result["content"][0]["text"], "main.rs\nsrc/");
+    }
+
+    #[test]
+    fn test_tools_list() {
+        let response = request(r#"{"jsonrpc":"2.0","id":6,"method":"tools/list"}"#);
+        let names: Vec<&str> = response["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["find_files", "list_dir"]);
+    }
+}
@@ -0,0 +1,474 @@
+use crate::search::SearchResult;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+/// How results are written to stdout.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum OutputFormat {
+    /// One path per line (what Claude Code expects), quoted if the path
+    /// would not survive as a line (see [`quote_path`]), followed by the
+    /// line anchor of a pasted path ("src/x.rs#L10")
+    #[default]
+    Lines,
+    /// Exact paths, each followed by a NUL byte (like `fd -0`)
+    Nul,
+    /// One JSON object per line with per-result metadata
+    Json,
+    /// Human-readable ranking: highlighted matches, the parts of each
+    /// result's sort key (group, nucleo score, walked path length) and why it is
+    /// below the one before it
+    Explain,
+}
+
+/// Write results in the requested format.
+pub fn write_results<W: Write>(
+    writer: &mut W,
+    format: OutputFormat,
+    results: &[SearchResult],
+) -> io::Result<()> {
+    for (index, result) in results.iter().enumerate() {
+        match format {
+            OutputFormat::Lines => match result.line {
+                Some(line) => writeln!(writer, "{}{line}", quote_path(result))?,
+                None => writeln!(writer, "{}", quote_path(result))?,
+            },
+            OutputFormat::Nul => {
+                let path = match &result.raw_path {
+                    Some(raw) => raw.as_os_str().as_encoded_bytes(),
+                    None => result.path.as_bytes(),
+                };
+                writer.write_all(path)?;
+                writer.write_all(b"\0")?;
+            }
+            OutputFormat::Json => {
+                serde_json::to_writer(&mut *writer, result)?;
+                writeln!(writer)?;
+            }
+            OutputFormat::Explain => {
+                let previous = index.checked_sub(1).map(|i| (i + 1, &results[i]));
+                writeln!(
+                    writer,
+                    "{:>3}. {}{}",
+                    index + 1,
+                    highlight(&result.path, &result.positions),
+                    result.line.map(|line| line.to_string()).unwrap_or_default()
+                )?;
+                let group = group(result);
+                writeln!(
+                    writer,
+                    "     {group}, nucleo score {}, length {}{}; {}",
+                    result.score,
+                    result.key_len,
+                    // Pasted and import targets rank first even if ignored
+                    if result.ignored && group != "ignored" {
+                        ", ignored"
+                    } else {
+                        ""
+                    },
+                    ranking_reason(previous, result)
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A result's path as printed in line output. Paths containing control
+/// characters or bytes that are not UTF-8, or starting with a double quote,
+/// are quoted as git quotes them: in double quotes, with C escapes for `"`,
+/// `\` and common control characters, and octal escapes for other bytes.
+/// Everything else, including non-ASCII names, is printed as is.
+pub fn quote_path(result: &SearchResult) -> Cow<'_, str> {
+    let path = &result.path;
+    if result.raw_path.is_none() && !path.starts_with('"') && !path.chars().any(char::is_control) {
+        return Cow::Borrowed(path);
+    }
+    let bytes = match &result.raw_path {
+        Some(raw) => raw.as_os_str().as_encoded_bytes(),
+        None => path.as_bytes(),
+    };
+
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('"');
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\x07' => out.push_str("\\a"),
+                '\x08' => out.push_str("\\b"),
+                '\t' => out.push_str("\\t"),
+                '\n' => out.push_str("\\n"),
+                '\x0b' => out.push_str("\\v"),
+                '\x0c' => out.push_str("\\f"),
+                '\r' => out.push_str("\\r"),
+                c if c.is_control() => {
+                    for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                        let _ = write!(out, "\\{byte:03o}");
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            let _ = write!(out, "\\{byte:03o}");
+        }
+    }
+    out.push('"');
+    Cow::Owned(out)
+}
+
+/// Wrap each run of matched characters in brackets, e.g. "src/[main].rs".
+/// Control characters are escaped so each result stays on one line.
+fn highlight(path: &str, positions: &[u32]) -> String {
+    let mut out = String::with_capacity(path.len() + positions.len() * 2);
+    let mut open = false;
+    for (i, c) in path.chars().enumerate() {
+        let matched = positions.binary_search(&(i as u32)).is_ok();
+        if matched != open {
+            out.push(if matched { '[' } else { ']' });
+            open = matched;
+        }
+        if c.is_control() {
+            out.extend(c.escape_default());
+        } else {
+            out.push(c);
+        }
+    }
+    if open {
+        out.push(']');
+    }
+    out
+}
+
+/// The group a result is ranked in, the first part of its sort key.
+fn group(result: &SearchResult) -> &'static str {
+    if result.typo {
+        "did you mean"
+    } else if result.sensitive {
+        "sensitive"
+    } else if result.pasted {
+        "pasted"
+    } else if result.import {
+        "import"
+    } else if result.ignored {
+        "ignored"
+    } else {
+        "visible"
+    }
+}
+
+/// Why a result ranks directly below `previous` (its rank and result),
+/// following the order used by the search: pasted-path and import targets
+/// before visible before ignored before sensitive before near misses, then
+/// score, then shorter path, then name.
+fn ranking_reason(previous: Option<(usize, &SearchResult)>, result: &SearchResult) -> String {
+    let Some((rank, previous)) = previous else {
+        return "top result".to_string();
+    };
+    if result.typo && !previous.typo {
+        return "near miss for a possible typo, below all matches".to_string();
+    }
+    if result.typo {
+        return format!("a further near miss, after #{rank}");
+    }
+    if result.sensitive && !previous.sensitive {
+        return "sensitive files rank after all others".to_string();
+    }
+    if previous.pasted && !result.pasted {
+        return "below the closest local match for the pasted path".to_string();
+    }
+    if result.pasted {
+        return format!("shares as much of the pasted path as #{rank}");
+    }
+    if previous.import && !result.import {
+        return "below the files the import path resolves to".to_string();
+    }
+    if result.import {
+        return format!("another file the import path resolves to, after #{rank}");
+    }
+    if result.ignored && !previous.ignored {
+        return "ignored results rank after all others".to_string();
+    }
+    if result.score < previous.score {
+        return format!("{} below #{rank}", previous.score - result.score);
+    }
+    if result.score == 0 {
+        return "no query, listed by path".to_string();
+    }
+    if result.key_len > previous.key_len {
+        format!("tied with #{rank}, longer path")
+    } else {
+        format!("tied with #{rank} on score and length, sorted by name")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::LineAnchor;
+    use crate::walker::EntryKind;
+
+    fn sample() -> Vec<SearchResult> {
+        vec![
+            SearchResult {
+                path: "src/main.rs".to_string(),
+                kind: EntryKind::File,
+                score: 120,
+                key_len: 11,
+                positions: vec![4, 5],
+                ignored: false,
+                sensitive: false,
+                import: false,
+                pasted: false,
+                line: None,
+                typo: false,
+                raw_path: None,
+            },
+            SearchResult {
+                path: "../.env.local".to_string(),
+                kind: EntryKind::File,
+                score: 80,
+                key_len: 10,
+                positions: vec![],
+                ignored: true,
+                sensitive: false,
+                import: false,
+                pasted: false,
+                line: None,
+                typo: false,
+                raw_path: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_lines_format() {
+        let mut out = Vec::new();
+        write_results(&mut out, OutputFormat::Lines, &sample()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "src/main.rs\n../.env.local\n"
+        );
+    }
+
+    #[test]
+    fn test_quote_path() {
+        let quoted = |path: &str| {
+            let mut result = sample().remove(0);
+            result.path = path.to_string();
+            quote_path(&result).into_owned()
+        };
+        assert_eq!(quoted("src/main.rs"), "src/main.rs");
+        assert_eq!(quoted("日本/ü.rs"), "日本/ü.rs");
+        assert_eq!(quoted("a\nb.txt"), r#""a\nb.txt""#);
+        assert_eq!(quoted("tab\there\\x"), r#""tab\there\\x""#);
+        assert_eq!(quoted("\"quoted\""), r#""\"quoted\"""#);
+        assert_eq!(quoted("esc\x1b.txt"), r#""esc\033.txt""#);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_quote_path_non_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut result = sample().remove(0);
+        result.path = "src/caf\u{fffd}.rs".to_string();
+        result.raw_path = Some(OsStr::from_bytes(b"src/caf\xe9.rs").into());
+        assert_eq!(quote_path(&result), r#""src/caf\351.rs""#);
+
+        let mut out = Vec::new();
+        write_results(&mut out, OutputFormat::Nul, &[result, sample().remove(0)]).unwrap();
+        assert_eq!(out, b"src/caf\xe9.rs\0src/main.rs\0");
+    }
+
+    #[test]
+    fn test_explain_uses_matched_length() {
+        // Prefixes and multi-byte names make the shown path a poor guide:
+        // both walked paths ("x/é.rs", "y/ab.rs") are 7 bytes
+        let mut results = sample();
+        results.truncate(1);
+        results[0].path = "../x/é.rs".to_string();
+        results[0].key_len = 7;
+        let mut second = results[0].clone();
+        second.path = "../y/ab.rs".to_string();
+        results.push(second);
+
+        let mut out = Vec::new();
+        write_results(&mut out, OutputFormat::Explain, &results).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out.lines().nth(3),
+            Some(
+                "     visible, nucleo score 120, length 7; tied with #1 on score and length, sorted by name"
+            )
+        );
+    }
+
+    #[test]
+    fn test_highlight_escapes_control_characters() {
+        assert_eq!(highlight("a\nmain", &[2, 3]), "a\\n[ma]in");
+    }
+
+    #[test]
+    fn test_highlight_runs() {
+        assert_eq!(highlight("src/main.rs", &[4, 5, 6, 7]), "src/[main].rs");
+        assert_eq!(highlight("src/main.rs", &[0, 4, 10]), "[s]rc/[m]ain.r[s]");
+        assert_eq!(highlight("main.rs", &[]), "main.rs");
+        // Positions are char indices, not byte offsets
+        assert_eq!(highlight("日本/main", &[3, 4]), "日本/[ma]in");
+    }
+
+    #[test]
+    fn test_explain_format() {
+        let mut results = sample();
+        results.insert(
+            1,
+            SearchResult {
+                path: "tests/main.rs".to_string(),
+                kind: EntryKind::File,
+                score: 120,
+                key_len: 13,
+                positions: vec![6, 7],
+                ignored: false,
+                sensitive: false,
+                import: false,
+                pasted: false,
+                line: None,
+                typo: false,
+                raw_path: None,
+            },
+        );
+        results.push(SearchResult {
+            path: ".env".to_string(),
+            kind: EntryKind::File,
+            score: 90,
+            key_len: 4,
+            positions: vec![],
+            ignored: false,
+            sensitive: true,
+            import: false,
+            pasted: false,
+            line: None,
+            typo: false,
+            raw_path: None,
+        });
+        let mut out = Vec::new();
+        write_results(&mut out, OutputFormat::Explain, &results).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "  1. src/[ma]in.rs",
+                "     visible, nucleo score 120, length 11; top result",
+                "  2. tests/[ma]in.rs",
+                "     visible, nucleo score 120, length 13; tied with #1, longer path",
+                "  3. ../.env.local",
+                "     ignored, nucleo score 80, length 10; ignored results rank after all others",
+                "  4. .env",
+                "     sensitive, nucleo score 90, length 4; sensitive files rank after all others",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ranking_reason_imports() {
+        let mut results = sample();
+        results[0].import = true;
+        results[1].ignored = false;
+        assert_eq!(
+            ranking_reason(Some((1, &results[0])), &results[1]),
+            "below the files the import path resolves to"
+        );
+        results[1].import = true;
+        assert_eq!(
+            ranking_reason(Some((1, &results[0])), &results[1]),
+            "another file the import path resolves to, after #1"
+        );
+    }
+
+    #[test]
+    fn test_ranking_reason_pasted() {
+        let mut results = sample();
+        results[0].pasted = true;
+        results[1].ignored = false;
+        assert_eq!(
+            ranking_reason(Some((1, &results[0])), &results[1]),
+            "below the closest local match for the pasted path"
+        );
+        results[1].pasted = true;
+        assert_eq!(
+            ranking_reason(Some((1, &results[0])), &results[1]),
+            "shares as much of the pasted path as #1"
+        );
+    }
+
+    #[test]
+    fn test_ranking_reason_typos() {
+        let mut results = sample();
+        results[1].typo = true;
+        assert_eq!(
+            ranking_reason(Some((1, &results[0])), &results[1]),
+            "near miss for a possible typo, below all matches"
+        );
+        results[0].typo = true;
+        assert_eq!(
+            ranking_reason(Some((1, &results[0])), &results[1]),
+            "a further near miss, after #1"
+        );
+    }
+
+    #[test]
+    fn test_lines_format_line_anchor() {
+        let mut results = sample();
+        results[0].line = Some(LineAnchor {
+            start: 10,
+            end: Some(20),
+        });
+        let mut out = Vec::new();
+        write_results(&mut out, OutputFormat::Lines, &results[..1]).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "src/main.rs#L10-20\n");
+    }
+
+    #[test]
+    fn test_ranking_reason_score_gap() {
+        let mut results = sample();
+        results[1].ignored = false;
+        assert_eq!(
+            ranking_reason(Some((1, &results[0])), &results[1]),
+            "40 below #1"
+        );
+    }
+
+    #[test]
+    fn test_json_format_marks_sensitive() {
+        let mut results = sample();
+        results[1].sensitive = true;
+        let mut out = Vec::new();
+        write_results(&mut out, OutputFormat::Json, &results[1..]).unwrap();
+        let line: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(line["sensitive"], true);
+    }
+
+    #[test]
+    fn test_json_format_marks_ignored() {
+        let mut out = Vec::new();
+        write_results(&mut out, OutputFormat::Json, &sample()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"path":"src/main.rs","kind":"file","score":120,"positions":[4,5],"ignored":false}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"path":"../.env.local","kind":"file","score":80,"positions":[],"ignored":true}"#
+        );
+    }
+}
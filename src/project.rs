@@ -0,0 +1,157 @@
+use crate::repo;
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
+
+/// Files marking the root of a project that is not a git checkout.
+const ROOT_MARKERS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "go.mod",
+    "pom.xml",
+    "build.gradle",
+    "build.gradle.kts",
+    "Gemfile",
+    "composer.json",
+    "mix.exs",
+    "deno.json",
+];
+
+/// Where queries without a "../" prefix are searched from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SearchRoot {
+    /// The working directory only
+    #[default]
+    Cwd,
+    /// The whole project, with paths still relative to the working directory
+    Project,
+}
+
+/// Detect the project containing `cwd`: the git work tree if there is one,
+/// otherwise the nearest directory with a marker file like `Cargo.toml`.
+pub fn find_root(cwd: &Path) -> Option<PathBuf> {
+    if let Some(work_tree) = repo::discover(cwd).and_then(|r| r.work_tree) {
+        return Some(work_tree);
+    }
+    cwd.ancestors()
+        .find(|dir| ROOT_MARKERS.iter().any(|marker| dir.join(marker).is_file()))
+        .map(Path::to_path_buf)
+}
+
+/// Rewrite a root-relative path to be relative to `cwd_rel` (the working
+/// directory, itself relative to the root), adding "../" as needed.
+///
+/// Returns `None` for the working directory and its ancestors, which are
+/// not useful suggestions.
+pub fn relative_to(cwd_rel: &str, path: &str) -> Option<String> {
+    let cwd_parts: Vec<&str> = cwd_rel.split('/').filter(|p| !p.is_empty()).collect();
+    let path_parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+
+    let common = cwd_parts
+        .iter()
+        .zip(&path_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == path_parts.len() {
+        return None;
+    }
+
+    let ups = cwd_parts.len() - common;
+    Some(format!(
+        "{}{}",
+        "../".repeat(ups),
+        path_parts[common..].join("/")
+    ))
+}
+
+/// Resolve `.` and `..` components without touching the filesystem.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_relative_to_inside_cwd() {
+        assert_eq!(
+            relative_to("src/api", "src/api/handler.rs").unwrap(),
+            "handler.rs"
+        );
+        assert_eq!(relative_to("", "src/main.rs").unwrap(), "src/main.rs");
+    }
+
+    #[test]
+    fn test_relative_to_sibling_and_root() {
+        assert_eq!(relative_to("src/api", "src/lib.rs").unwrap(), "../lib.rs");
+        assert_eq!(
+            relative_to("src/api", "README.md").unwrap(),
+            "../../README.md"
+        );
+        assert_eq!(
+            relative_to("src/api", "tests/api.rs").unwrap(),
+            "../../tests/api.rs"
+        );
+    }
+
+    #[test]
+    fn test_relative_to_shared_name_prefix() {
+        // "src/apis" is not inside "src/api"
+        assert_eq!(
+            relative_to("src/api", "src/apis/x.rs").unwrap(),
+            "../apis/x.rs"
+        );
+    }
+
+    #[test]
+    fn test_relative_to_cwd_and_ancestors() {
+        assert_eq!(relative_to("src/api", "src/api"), None);
+        assert_eq!(relative_to("src/api", "src"), None);
+    }
+
+    #[test]
+    fn test_find_root_git() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::create_dir_all(dir.path().join("crates/core/src")).unwrap();
+        // A nested marker does not win over the git root
+        fs::write(dir.path().join("crates/core/Cargo.toml"), "").unwrap();
+
+        let root = find_root(&dir.path().join("crates/core/src")).unwrap();
+        assert_eq!(root, dir.path());
+    }
+
+    #[test]
+    fn test_find_root_marker() {
+        let dir = TempDir::new().unwrap();
+        // Only meaningful if the temp dir itself is not inside a repository
+        if repo::discover(dir.path()).is_some() {
+            return;
+        }
+        fs::create_dir_all(dir.path().join("app/src/components")).unwrap();
+        fs::write(dir.path().join("app/package.json"), "{}").unwrap();
+
+        let root = find_root(&dir.path().join("app/src/components")).unwrap();
+        assert_eq!(root, dir.path().join("app"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(Path::new("/a/b/./../c")), PathBuf::from("/a/c"));
+    }
+}
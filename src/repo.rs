@@ -0,0 +1,322 @@
+use ignore::Match;
+use ignore::gitignore::{Gitignore, GitignoreBuilder, Glob};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A git repository enclosing a search base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repository {
+    /// Root of the checkout (`None` for bare repositories)
+    pub work_tree: Option<PathBuf>,
+    /// The repository's git dir (`.git/worktrees/<name>` for linked worktrees)
+    pub git_dir: PathBuf,
+    /// Git dir shared by all worktrees, holding `info/exclude`
+    pub common_dir: PathBuf,
+    /// Whether the repository was given by `GIT_DIR` / `GIT_WORK_TREE`, in
+    /// which case the work tree need not contain a `.git` entry
+    pub from_env: bool,
+}
+
+/// `info/exclude` rules of a repository, anchored at its work tree.
+pub struct Excludes {
+    pub work_tree: PathBuf,
+    pub rules: Gitignore,
+}
+
+impl Excludes {
+    /// Load `<common_dir>/info/exclude` for the checkout at `work_tree`.
+    pub fn load(work_tree: &Path, common_dir: &Path) -> Self {
+        let mut builder = GitignoreBuilder::new(work_tree);
+        builder.add(common_dir.join("info").join("exclude"));
+        Self {
+            work_tree: work_tree.to_path_buf(),
+            rules: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+        }
+    }
+
+    /// Whether a path strictly inside the work tree is excluded.
+    pub fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        self.matched(path, is_dir).is_ignore()
+    }
+
+    /// The rule matching a path strictly inside the work tree.
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Match<&Glob> {
+        match path.strip_prefix(&self.work_tree) {
+            Ok(inner) => self.rules.matched(inner, is_dir),
+            Err(_) => Match::None,
+        }
+    }
+}
+
+impl Repository {
+    /// Exclude rules of this repository, if it has a work tree.
+    pub fn excludes(&self) -> Option<Excludes> {
+        let work_tree = self.work_tree.as_deref()?;
+        Some(Excludes::load(work_tree, &self.common_dir))
+    }
+}
+
+/// Find the repository enclosing `start`, honouring `GIT_DIR` and
+/// `GIT_WORK_TREE` like git does.
+pub fn discover(start: &Path) -> Option<Repository> {
+    let current = env::current_dir().unwrap_or_default();
+    let from_env = |name| {
+        env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(|value| current.join(value))
+    };
+    discover_with(start, from_env("GIT_DIR"), from_env("GIT_WORK_TREE"))
+}
+
+/// Discovery with explicit `GIT_DIR` / `GIT_WORK_TREE` values.
+pub fn discover_with(
+    start: &Path,
+    git_dir_env: Option<PathBuf>,
+    work_tree_env: Option<PathBuf>,
+) -> Option<Repository> {
+    if let Some(git_dir) = git_dir_env {
+        // Without GIT_WORK_TREE git uses core.worktree, or the current
+        // directory unless the repository is bare
+        let work_tree = work_tree_env.or_else(|| {
+            let config = read_config(&git_dir);
+            match config_value(&config, "worktree") {
+                Some(path) => Some(git_dir.join(path)),
+                None if config_value(&config, "bare") == Some("true") => None,
+                None => Some(start.to_path_buf()),
+            }
+        });
+        return Some(Repository {
+            work_tree,
+            common_dir: common_dir(&git_dir),
+            git_dir,
+            from_env: true,
+        });
+    }
+
+    for dir in start.ancestors() {
+        if let Some(git_dir) = resolve_git_dir(dir) {
+            return Some(Repository {
+                work_tree: Some(dir.to_path_buf()),
+                common_dir: common_dir(&git_dir),
+                git_dir,
+                from_env: false,
+            });
+        }
+        if looks_like_git_dir(dir) {
+            // A bare repository, or the inside of a `.git` directory
+            return Some(Repository {
+                work_tree: None,
+                common_dir: common_dir(dir),
+                git_dir: dir.to_path_buf(),
+                from_env: false,
+            });
+        }
+    }
+    None
+}
+
+/// Directory a `.git` entry points to: the directory itself, or the target
+/// of a `gitdir:` file (resolved relative to `repo_dir`, as git does).
+pub fn resolve_git_dir(repo_dir: &Path) -> Option<PathBuf> {
+    let dot_git = repo_dir.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let contents = fs::read_to_string(&dot_git).ok()?;
+    let target = contents.lines().next()?.strip_prefix("gitdir:")?.trim();
+    Some(repo_dir.join(target))
+}
+
+/// The shared git dir named by a linked worktree's `commondir` file.
+pub fn common_dir(git_dir: &Path) -> PathBuf {
+    fs::read_to_string(git_dir.join("commondir"))
+        .ok()
+        .and_then(|contents| contents.lines().next().map(str::trim).map(PathBuf::from))
+        .filter(|path| !path.as_os_str().is_empty())
+        .map(|path| git_dir.join(path))
+        .unwrap_or_else(|| git_dir.to_path_buf())
+}
+
+/// Whether `dir` has the layout of a git dir (HEAD, objects/, refs/).
+fn looks_like_git_dir(dir: &Path) -> bool {
+    dir.join("HEAD").is_file() && dir.join("objects").is_dir() && dir.join("refs").is_dir()
+}
+
+fn read_config(git_dir: &Path) -> String {
+    fs::read_to_string(git_dir.join("config")).unwrap_or_default()
+}
+
+/// Value of a key in the `[core]` section of a git config file.
+fn config_value<'a>(config: &'a str, key: &str) -> Option<&'a str> {
+    let mut in_core = false;
+    for line in config.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_core = line.eq_ignore_ascii_case("[core]");
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if in_core && name.trim().eq_ignore_ascii_case(key) {
+            return Some(value.trim());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn init_git_dir(git_dir: &Path) {
+        fs::create_dir_all(git_dir.join("objects")).unwrap();
+        fs::create_dir_all(git_dir.join("refs")).unwrap();
+        fs::create_dir_all(git_dir.join("info")).unwrap();
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+    }
+
+    #[test]
+    fn test_discover_regular_repo_from_subdir() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        init_git_dir(&root.join(".git"));
+        fs::create_dir_all(root.join("src/api")).unwrap();
+
+        let repo = discover_with(&root.join("src/api"), None, None).unwrap();
+        assert_eq!(repo.work_tree.as_deref(), Some(root));
+        assert_eq!(repo.git_dir, root.join(".git"));
+        assert_eq!(repo.common_dir, root.join(".git"));
+        assert!(!repo.from_env);
+    }
+
+    #[test]
+    fn test_discover_linked_worktree() {
+        let dir = TempDir::new().unwrap();
+        let main_git = dir.path().join("main/.git");
+        init_git_dir(&main_git);
+        let wt_git = main_git.join("worktrees/feature");
+        fs::create_dir_all(&wt_git).unwrap();
+        fs::write(wt_git.join("commondir"), "../..\n").unwrap();
+
+        let worktree = dir.path().join("feature");
+        fs::create_dir_all(worktree.join("src")).unwrap();
+        fs::write(
+            worktree.join(".git"),
+            "gitdir: ../main/.git/worktrees/feature\n",
+        )
+        .unwrap();
+
+        let repo = discover_with(&worktree.join("src"), None, None).unwrap();
+        assert_eq!(repo.work_tree.as_deref(), Some(worktree.as_path()));
+        assert_eq!(
+            repo.git_dir,
+            worktree.join("../main/.git/worktrees/feature")
+        );
+        assert_eq!(
+            repo.common_dir.canonicalize().unwrap(),
+            main_git.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_discover_bare_repo() {
+        let dir = TempDir::new().unwrap();
+        let bare = dir.path().join("project.git");
+        init_git_dir(&bare);
+
+        let repo = discover_with(&bare.join("refs"), None, None).unwrap();
+        assert_eq!(repo.work_tree, None);
+        assert_eq!(repo.git_dir, bare);
+        assert!(repo.excludes().is_none());
+    }
+
+    #[test]
+    fn test_discover_from_env() {
+        let dir = TempDir::new().unwrap();
+        let git_dir = dir.path().join("store/project.git");
+        init_git_dir(&git_dir);
+        let work_tree = dir.path().join("checkout");
+        fs::create_dir_all(work_tree.join("src")).unwrap();
+
+        let repo = discover_with(
+            &work_tree.join("src"),
+            Some(git_dir.clone()),
+            Some(work_tree.clone()),
+        )
+        .unwrap();
+        assert_eq!(repo.work_tree, Some(work_tree.clone()));
+        assert_eq!(repo.git_dir, git_dir);
+        assert!(repo.from_env);
+
+        // GIT_DIR alone: core.worktree, then bare, then the start directory
+        let repo = discover_with(&work_tree, Some(git_dir.clone()), None).unwrap();
+        assert_eq!(repo.work_tree, Some(work_tree.clone()));
+
+        fs::write(git_dir.join("config"), "[core]\n\tbare = true\n").unwrap();
+        let repo = discover_with(&work_tree, Some(git_dir.clone()), None).unwrap();
+        assert_eq!(repo.work_tree, None);
+
+        fs::write(
+            git_dir.join("config"),
+            "[core]\n\tworktree = ../../checkout\n",
+        )
+        .unwrap();
+        let repo = discover_with(dir.path(), Some(git_dir.clone()), None).unwrap();
+        assert_eq!(repo.work_tree, Some(git_dir.join("../../checkout")));
+    }
+
+    #[test]
+    fn test_resolve_git_dir_from_file() {
+        let dir = TempDir::new().unwrap();
+        let sub = dir.path().join("vendor/libfoo");
+        fs::create_dir_all(&sub).unwrap();
+        fs::write(sub.join(".git"), "gitdir: ../../.git/modules/libfoo\n").unwrap();
+
+        assert_eq!(
+            resolve_git_dir(&sub),
+            Some(sub.join("../../.git/modules/libfoo"))
+        );
+        assert_eq!(resolve_git_dir(dir.path()), None);
+    }
+
+    #[test]
+    fn test_discover_outside_repo() {
+        let dir = TempDir::new().unwrap();
+        // Only meaningful if the temp dir itself is not inside a repository
+        if dir
+            .path()
+            .ancestors()
+            .skip(1)
+            .any(|d| d.join(".git").exists())
+        {
+            return;
+        }
+        assert_eq!(discover_with(dir.path(), None, None), None);
+    }
+
+    #[test]
+    fn test_excludes_from_common_dir() {
+        let dir = TempDir::new().unwrap();
+        let git_dir = dir.path().join("repo.git");
+        init_git_dir(&git_dir);
+        fs::write(git_dir.join("info/exclude"), "*.secret\nscratch/\n").unwrap();
+        let work_tree = dir.path().join("checkout");
+
+        let excludes = Excludes::load(&work_tree, &git_dir);
+        assert!(excludes.is_excluded(&work_tree.join("a.secret"), false));
+        assert!(excludes.is_excluded(&work_tree.join("scratch"), true));
+        assert!(!excludes.is_excluded(&work_tree.join("a.rs"), false));
+        // Paths outside the work tree are never excluded
+        assert!(!excludes.is_excluded(&dir.path().join("a.secret"), false));
+    }
+
+    #[test]
+    fn test_config_value() {
+        let config = "[core]\n\tbare = false\n[remote \"origin\"]\n\tworktree = x\n";
+        assert_eq!(config_value(config, "bare"), Some("false"));
+        assert_eq!(config_value(config, "worktree"), None);
+    }
+}
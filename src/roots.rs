@@ -0,0 +1,133 @@
+use crate::config;
+use crate::project;
+use std::path::{Component, Path, PathBuf};
+
+/// A directory searched alongside the working directory, such as a sibling
+/// repository the session also works in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Root {
+    pub dir: PathBuf,
+    /// Prefix that makes paths below `dir` relative to the working directory
+    /// ("../api/"), or absolute ("/srv/data/") when they only share `/`
+    pub prefix: String,
+}
+
+/// Resolve additional roots against `cwd` (and `~/` against the home
+/// directory), dropping missing directories, duplicates and `cwd` itself.
+pub(crate) fn resolve(cwd: &Path, dirs: &[PathBuf]) -> Vec<Root> {
+    let cwd = project::normalize(cwd);
+    let home = config::home_dir();
+    let mut roots: Vec<Root> = Vec::new();
+    for dir in dirs {
+        let dir = match (dir.strip_prefix("~"), &home) {
+            (Ok(rest), Some(home)) => home.join(rest),
+            _ => cwd.join(dir),
+        };
+        let dir = project::normalize(&dir);
+        if dir == cwd || !dir.is_dir() || roots.iter().any(|root| root.dir == dir) {
+            continue;
+        }
+        roots.push(Root {
+            prefix: display_prefix(&cwd, &dir),
+            dir,
+        });
+    }
+    roots
+}
+
+/// Whether a `root:` token names `dir`, by final component or full path.
+pub(crate) fn matches(dir: &Path, token: &str) -> bool {
+    let token = token.trim_end_matches(['/', '\\']);
+    dir.file_name().is_some_and(|name| name == token) || dir == Path::new(token)
+}
+
+/// The prefix shown before paths below `dir`: relative to `cwd` when the two
+/// share more than the filesystem root, absolute otherwise.
+pub(crate) fn display_prefix(cwd: &Path, dir: &Path) -> String {
+    let cwd_parts: Vec<Component> = cwd.components().collect();
+    let dir_parts: Vec<Component> = dir.components().collect();
+    let common = cwd_parts
+        .iter()
+        .zip(&dir_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let shares_only_root = dir_parts[..common]
+        .iter()
+        .all(|c| matches!(c, Component::RootDir | Component::Prefix(_)));
+    if shares_only_root {
+        let absolute = dir.to_string_lossy().replace('\\', "/");
+        return format!("{}/", absolute.trim_end_matches('/'));
+    }
+
+    let mut prefix = "../".repeat(cwd_parts.len() - common);
+    for part in &dir_parts[common..] {
+        prefix.push_str(&part.as_os_str().to_string_lossy());
+        prefix.push('/');
+    }
+    prefix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_display_prefix_sibling() {
+        let cwd = Path::new("/home/user/web");
+        assert_eq!(display_prefix(cwd, Path::new("/home/user/api")), "../api/");
+        assert_eq!(
+            display_prefix(cwd, Path::new("/home/user/libs/core")),
+            "../libs/core/"
+        );
+        assert_eq!(
+            display_prefix(cwd, Path::new("/home/user/web/docs")),
+            "docs/"
+        );
+        assert_eq!(display_prefix(cwd, Path::new("/home/user")), "../");
+    }
+
+    #[test]
+    fn test_display_prefix_absolute() {
+        let cwd = Path::new("/home/user/web");
+        assert_eq!(display_prefix(cwd, Path::new("/srv/data")), "/srv/data/");
+        assert_eq!(display_prefix(cwd, Path::new("/")), "/");
+    }
+
+    #[test]
+    fn test_matches() {
+        let dir = Path::new("/home/user/api");
+        assert!(matches(dir, "api"));
+        assert!(matches(dir, "api/"));
+        assert!(matches(dir, "/home/user/api"));
+        assert!(!matches(dir, "user"));
+        assert!(!matches(dir, "ap"));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path();
+        fs::create_dir_all(base.join("web")).unwrap();
+        fs::create_dir_all(base.join("api")).unwrap();
+        let cwd = base.join("web");
+
+        let roots = resolve(
+            &cwd,
+            &[
+                PathBuf::from("../api"),
+                base.join("api"),
+                PathBuf::from("."),
+                PathBuf::from("../missing"),
+            ],
+        );
+        assert_eq!(
+            roots,
+            vec![Root {
+                dir: base.join("api"),
+                prefix: "../api/".to_string(),
+            }]
+        );
+    }
+}
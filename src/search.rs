@@ -0,0 +1,833 @@
+use crate::config::{self, Config};
+use crate::doctor::{self, Diagnosis};
+use crate::imports;
+use crate::input::{self, Input, LineAnchor, ParsedQuery};
+use crate::matcher::{self, FuzzyMatcher, MIN_MATCHES};
+use crate::project::{self, SearchRoot};
+use crate::roots::{self, Root};
+use crate::sensitive::{SensitiveFiles, SensitivePolicy};
+use crate::settings::DenyRules;
+use crate::submodules::{self, SubmodulePolicy};
+use crate::walker::{self, Entry, EntryKind, Limits, SymlinkPolicy, WalkConfig};
+use serde::{Serialize, Serializer};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Default maximum number of results (what Claude Code shows).
+pub const DEFAULT_LIMIT: usize = 50;
+
+/// A ranked search result.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct SearchResult {
+    /// Path relative to the working directory, including any "../" prefix
+    pub path: String,
+    pub kind: EntryKind,
+    /// Fuzzy match score (0 for listings without a pattern)
+    pub score: u32,
+    /// Byte length of the walked path, without any "../" or root prefix,
+    /// which breaks ties between equal scores (shorter first)
+    #[serde(skip)]
+    pub key_len: usize,
+    /// Sorted char indices into `path` of the matched characters
+    pub positions: Vec<u32>,
+    /// Whether the path is normally hidden by ignore rules or SKIP_DIRS
+    pub ignored: bool,
+    /// Whether the path matches a sensitive-file pattern (only with
+    /// [`SensitivePolicy::Flag`])
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub sensitive: bool,
+    /// Whether the query is an import path (e.g. "crate::walker") that
+    /// resolves to this file; such results rank first
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub import: bool,
+    /// Whether the query is a pasted URL or foreign path (e.g. from a CI
+    /// log) and this file shares its longest suffix; such results rank first
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub pasted: bool,
+    /// Line anchor of the pasted path, on pasted results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<LineAnchor>,
+    /// Whether this is a "did you mean" near miss for a query that found
+    /// few matches (e.g. "cofnig" for "config.rs"); these rank last
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub typo: bool,
+    /// The exact path when it is not valid UTF-8 (`path` is then lossy),
+    /// serialized as `path_bytes`
+    #[serde(
+        rename = "path_bytes",
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_bytes"
+    )]
+    pub raw_path: Option<PathBuf>,
+}
+
+/// Write a path as its bytes (raw on Unix, WTF-8 on Windows).
+fn serialize_bytes<S: Serializer>(
+    path: &Option<PathBuf>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let bytes = path
+        .as_deref()
+        .map_or(&[][..], |p| p.as_os_str().as_encoded_bytes());
+    serializer.collect_seq(bytes)
+}
+
+/// Where a search spent its time, from [`Search::run_with_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SearchStats {
+    /// Directory that was walked (`None` if the query's directory does not exist)
+    pub search_base: Option<PathBuf>,
+    /// Additional roots walked alongside `search_base`
+    pub additional_roots: Vec<PathBuf>,
+    pub walk_time: Duration,
+    pub match_time: Duration,
+    /// Entries found by the walks, including ignored ones
+    pub candidates: usize,
+    pub ignored_candidates: usize,
+    /// Entries matching a sensitive-file pattern, hidden or ranked last
+    pub sensitive_candidates: usize,
+    /// Entries the walk could not read (permission errors, symlink loops)
+    pub walk_errors: usize,
+    /// A broad walk stopped at `Limits::max_entries`
+    pub walk_truncated: bool,
+}
+
+/// A file search: `parse_query` -> `walk_entries` -> `FuzzyMatcher`.
+///
+/// ```no_run
+/// use claude_search::Search;
+///
+/// let results = Search::new("/path/to/project")
+///     .query("../src/main")
+///     .limit(10)
+///     .run();
+/// for result in results {
+///     println!("{} ({})", result.path, result.score);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Search {
+    pub(crate) cwd: PathBuf,
+    query: String,
+    pub(crate) limit: usize,
+    include_ignored: bool,
+    symlinks: SymlinkPolicy,
+    pub(crate) submodules: SubmodulePolicy,
+    search_root: SearchRoot,
+    pub(crate) sensitive: SensitivePolicy,
+    pub(crate) sensitive_files: SensitiveFiles,
+    limits: Limits,
+    additional_roots: Vec<PathBuf>,
+    max_depth: Option<usize>,
+}
+
+impl Search {
+    /// A search from `cwd` with default settings and an empty query.
+    pub fn new(cwd: impl Into<PathBuf>) -> Self {
+        Self {
+            cwd: cwd.into(),
+            query: String::new(),
+            limit: DEFAULT_LIMIT,
+            include_ignored: false,
+            symlinks: SymlinkPolicy::default(),
+            submodules: SubmodulePolicy::default(),
+            search_root: SearchRoot::default(),
+            sensitive: SensitivePolicy::default(),
+            sensitive_files: SensitiveFiles::default(),
+            limits: Limits::default(),
+            additional_roots: Vec::new(),
+            max_depth: None,
+        }
+    }
+
+    /// A search for a stdin JSON request, with `config` as defaults for
+    /// anything the request leaves out.
+    pub fn from_input(input: Input, config: &Config) -> Self {
+        // Get cwd, default to current directory
+        let cwd = input
+            .cwd
+            .map(PathBuf::from)
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+        let additional_roots = config
+            .additional_roots
+            .iter()
+            .cloned()
+            .chain(input.additional_roots.into_iter().map(PathBuf::from))
+            .collect::<Vec<_>>();
+
+        Self::new(cwd)
+            .query(input.query.unwrap_or_default())
+            .limit(input.limit.unwrap_or(DEFAULT_LIMIT))
+            .include_ignored(input.include_ignored)
+            .symlinks(input.symlinks.unwrap_or(config.symlinks))
+            .submodules(input.submodules.unwrap_or(config.submodules))
+            .search_root(input.search_root.unwrap_or(config.search_root))
+            .sensitive(input.sensitive.unwrap_or(config.sensitive))
+            .sensitive_files(config.sensitive_files())
+            .limits(config.limits)
+            .additional_roots(additional_roots)
+    }
+
+    /// Apply the settings from a user config.
+    pub fn config(self, config: &Config) -> Self {
+        self.symlinks(config.symlinks)
+            .submodules(config.submodules)
+            .search_root(config.search_root)
+            .sensitive(config.sensitive)
+            .sensitive_files(config.sensitive_files())
+            .limits(config.limits)
+            .additional_roots(&config.additional_roots)
+    }
+
+    /// The raw query, as typed after "@" (e.g. "../src/main", "!!.env").
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.query = query.into();
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Also return gitignored and skipped files, ranked below the rest.
+    pub fn include_ignored(mut self, yes: bool) -> Self {
+        self.include_ignored = yes;
+        self
+    }
+
+    pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    pub fn submodules(mut self, submodules: SubmodulePolicy) -> Self {
+        self.submodules = submodules;
+        self
+    }
+
+    pub fn search_root(mut self, search_root: SearchRoot) -> Self {
+        self.search_root = search_root;
+        self
+    }
+
+    /// Hide secret-like files (the default), rank them last or show them.
+    pub fn sensitive(mut self, sensitive: SensitivePolicy) -> Self {
+        self.sensitive = sensitive;
+        self
+    }
+
+    /// Replace the built-in sensitive-file patterns.
+    pub fn sensitive_files(mut self, sensitive_files: SensitiveFiles) -> Self {
+        self.sensitive_files = sensitive_files;
+        self
+    }
+
+    /// Caps for walks that reach `/` or the home directory.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Directories searched alongside the working directory, such as sibling
+    /// repositories. Results from all roots are ranked together, with paths
+    /// relative to the working directory where they share more than `/`.
+    /// A leading "root:name" token in the query searches only one root.
+    pub fn additional_roots<P: AsRef<Path>>(mut self, dirs: impl IntoIterator<Item = P>) -> Self {
+        self.additional_roots = dirs.into_iter().map(|d| d.as_ref().to_path_buf()).collect();
+        self
+    }
+
+    /// Walk at most this deep instead of the default for the query (e.g. 1
+    /// to list a single directory). Walks from `/` or the home directory
+    /// stay within `limits` either way.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Run the search. Problems such as a missing directory yield no results.
+    pub fn run(&self) -> Vec<SearchResult> {
+        self.run_with_stats().0
+    }
+
+    /// Run the search, also reporting how long each phase took and how many
+    /// candidates the walk produced.
+    pub fn run_with_stats(&self) -> (Vec<SearchResult>, SearchStats) {
+        let mut stats = SearchStats::default();
+        let Some(plan) = self.plan() else {
+            return (Vec::new(), stats);
+        };
+        let Plan {
+            parsed,
+            cwd_in_root,
+            walk_config,
+            include_ignored,
+            roots,
+            imports,
+        } = plan;
+
+        // Walk files (ignored files only when explicitly requested), with
+        // additional roots walked concurrently
+        let started = Instant::now();
+        let walks = std::thread::scope(|scope| {
+            let handles: Vec<_> = roots
+                .iter()
+                .map(|(root, config)| {
+                    scope.spawn(move || {
+                        let deny = DenyRules::load(&root.dir);
+                        walker::walk_entries_counted(&root.dir, config, include_ignored, &deny)
+                    })
+                })
+                .collect();
+            let deny = DenyRules::load(&self.cwd);
+            let mut walks = vec![walker::walk_entries_counted(
+                &parsed.search_base,
+                &walk_config,
+                include_ignored,
+                &deny,
+            )];
+            walks.extend(handles.into_iter().map(|h| h.join().unwrap_or_default()));
+            walks
+        });
+        stats.search_base = Some(parsed.search_base.clone());
+        stats.additional_roots = roots.iter().map(|(root, _)| root.dir.clone()).collect();
+        stats.walk_time = started.elapsed();
+
+        // Work out what each entry is shown as, dropping entries an earlier
+        // root already found
+        let mut seen = HashSet::new();
+        let mut candidates = Vec::new();
+        for (i, walked) in walks.into_iter().enumerate() {
+            let entries = walked.entries;
+            stats.candidates += entries.len();
+            stats.walk_errors += walked.errors;
+            stats.walk_truncated |= walked.truncated;
+            let base = match i {
+                0 => &parsed.search_base,
+                _ => &roots[i - 1].0.dir,
+            };
+            for entry in entries {
+                if !roots.is_empty() && !seen.insert(base.join(&entry.path)) {
+                    continue;
+                }
+                let shown = match i {
+                    0 => display_path(&parsed, cwd_in_root.as_deref(), &entry.path),
+                    _ => Some(format!("{}{}", roots[i - 1].0.prefix, entry.path)),
+                };
+                if let Some(shown) = shown {
+                    let raw = entry
+                        .raw_path
+                        .as_deref()
+                        .map(|raw| raw_display(&shown, &entry.path, raw));
+                    let import = match imports.is_empty() {
+                        true => None,
+                        false => {
+                            let path = base.join(entry.os_path());
+                            imports.iter().position(|import| *import == path)
+                        }
+                    };
+                    let suffix = parsed
+                        .pasted
+                        .as_ref()
+                        .map_or(0, |pasted| shared_suffix(&entry.path, &pasted.path));
+                    candidates.push(Candidate {
+                        entry,
+                        shown,
+                        raw,
+                        import,
+                        suffix,
+                    });
+                }
+            }
+        }
+
+        // A pasted path resolves to the files sharing its longest suffix.
+        // One segment only counts for a file name: a URL ending in a
+        // repository name must not pin every file called that
+        let needed = match &parsed.pasted {
+            Some(pasted) if looks_like_file(&pasted.path) => 1,
+            _ => 2,
+        };
+        let longest = candidates
+            .iter()
+            .map(|c| c.suffix)
+            .max()
+            .filter(|&longest| longest >= needed)
+            .unwrap_or(0);
+        let is_pasted = |candidate: &Candidate| longest > 0 && candidate.suffix == longest;
+
+        // Hide sensitive files or set them aside to rank last; files an
+        // import or pasted path resolves to rank first
+        let mut imported = Vec::new();
+        let mut visible = Vec::new();
+        let mut ignored = Vec::new();
+        let mut sensitive = Vec::new();
+        for candidate in candidates {
+            let entry = &candidate.entry;
+            if self.sensitive != SensitivePolicy::Show
+                && self
+                    .sensitive_files
+                    .is_sensitive(&entry.path, entry.kind == EntryKind::Dir)
+            {
+                stats.sensitive_candidates += 1;
+                if self.sensitive == SensitivePolicy::Flag {
+                    sensitive.push(candidate);
+                }
+            } else if candidate.import.is_some() || is_pasted(&candidate) {
+                imported.push(candidate);
+            } else if entry.ignored {
+                ignored.push(candidate);
+            } else {
+                visible.push(candidate);
+            }
+        }
+        stats.ignored_candidates =
+            ignored.len() + imported.iter().filter(|c| c.entry.ignored).count();
+        // Without a pattern nothing is scored, so list by path rather than in
+        // the order the walk's threads happened to find entries
+        if parsed.pattern.is_empty() {
+            for group in [&mut visible, &mut ignored, &mut sensitive] {
+                group.sort_by(|a, b| a.shown.cmp(&b.shown));
+            }
+        }
+        imported.sort_by_key(|candidate| (candidate.import, candidate.shown.len()));
+
+        // Match and rank: pasted and import targets in resolution order, normal
+        // results, then ignored, then sensitive ones
+        let started = Instant::now();
+        let mut matcher = FuzzyMatcher::new();
+        let mut matched = Vec::new();
+        let mut missed = Vec::new();
+        let pattern = parsed.pattern.as_str();
+        for (group, pattern, is_sensitive) in [
+            (imported, "", false),
+            (visible, pattern, false),
+            (ignored, pattern, false),
+            (sensitive, pattern, true),
+        ] {
+            let remaining = self.limit - matched.len();
+            let (scored, rest) = matcher.match_split(group, pattern, remaining, Candidate::path);
+            matched.extend(
+                scored
+                    .into_iter()
+                    .map(|scored| (scored, is_sensitive, false)),
+            );
+            if !is_sensitive {
+                missed.push(rest);
+            }
+        }
+
+        // Too few matches, perhaps from a typo: suggest near misses below
+        // them, normal before ignored and never sensitive files
+        if matched.len() < MIN_MATCHES && parsed.pasted.is_none() {
+            for group in missed {
+                let remaining = self.limit - matched.len();
+                let typos = matcher::match_typos(group, pattern, remaining, Candidate::path);
+                matched.extend(typos.into_iter().map(|scored| (scored, false, true)));
+            }
+        }
+        stats.match_time = started.elapsed();
+
+        let line = parsed.pasted.as_ref().and_then(|pasted| pasted.line);
+        let results = matched
+            .into_iter()
+            .map(|(scored, sensitive, typo)| {
+                let pasted = is_pasted(&scored.item);
+                let Candidate {
+                    entry,
+                    shown,
+                    raw,
+                    import,
+                    suffix: _,
+                } = scored.item;
+                SearchResult {
+                    positions: rebase_positions(&scored.positions, &entry.path, &shown),
+                    path: shown,
+                    kind: entry.kind,
+                    score: scored.score,
+                    key_len: entry.path.len(),
+                    ignored: entry.ignored,
+                    sensitive,
+                    import: import.is_some(),
+                    pasted,
+                    line: line.filter(|_| pasted),
+                    typo,
+                    raw_path: raw,
+                }
+            })
+            .collect();
+        (results, stats)
+    }
+
+    /// Explain why `path` (relative to the working directory) is or is not
+    /// among the results, and where it ranks.
+    pub fn diagnose(&self, path: impl AsRef<Path>) -> Diagnosis {
+        doctor::diagnose(self, path.as_ref())
+    }
+
+    /// Work out where and how to walk, or `None` if there is nothing to search.
+    pub(crate) fn plan(&self) -> Option<Plan> {
+        let mut parsed = input::parse_query(&self.query, &self.cwd);
+        let cwd = project::normalize(&self.cwd);
+        let mut roots = roots::resolve(&cwd, &self.additional_roots);
+
+        // Scope to one additional root if requested, applying any "../" from
+        // there. Naming the working directory searches only it.
+        let mut scope = cwd.clone();
+        let mut scope_prefix = String::new();
+        if let Some(name) = &parsed.root
+            && !roots::matches(&cwd, name)
+        {
+            let root = roots.iter().find(|root| roots::matches(&root.dir, name))?;
+            let ascents = parsed.output_prefix.matches("../").count();
+            let base = root.dir.ancestors().nth(ascents).unwrap_or(&root.dir);
+            parsed.output_prefix = roots::display_prefix(&cwd, base);
+            parsed.search_base = base.to_path_buf();
+            scope = root.dir.clone();
+            scope_prefix = root.prefix.clone();
+        }
+
+        // Scope to a submodule if requested
+        if let Some(name) = &parsed.submodule {
+            let (dir, prefix) = submodules::locate(&scope, name)?;
+            parsed.search_base = dir;
+            parsed.output_prefix = format!("{scope_prefix}{prefix}");
+        }
+
+        // Additional roots are searched only for plain queries
+        if parsed.root.is_some() || parsed.submodule.is_some() || !parsed.output_prefix.is_empty() {
+            roots.clear();
+        }
+
+        // Verify search base exists
+        if !parsed.search_base.exists() {
+            return None;
+        }
+
+        // Search the whole project for plain queries if configured, remembering
+        // where cwd sits so paths can be made relative to it again
+        let mut cwd_in_root = None;
+        if self.search_root == SearchRoot::Project
+            && !parsed.is_empty
+            && parsed.output_prefix.is_empty()
+            && parsed.submodule.is_none()
+            && let Some(root) = project::find_root(&self.cwd)
+            && let Ok(rel) = self.cwd.strip_prefix(&root)
+            && let Some(rel) = rel.to_str()
+        {
+            cwd_in_root = Some(rel.replace('\\', "/"));
+            parsed.search_base = root;
+        }
+
+        // The walk starts higher up, so it goes as deep below cwd as usual
+        let below_root = cwd_in_root
+            .as_deref()
+            .filter(|rel| !rel.is_empty())
+            .map_or(0, |rel| rel.split('/').count());
+        let walk_config = self.walk_config(&parsed.search_base, parsed.is_empty, below_root);
+        let roots: Vec<(Root, WalkConfig)> = roots
+            .into_iter()
+            .map(|root| {
+                let config = self.walk_config(&root.dir, parsed.is_empty, 0);
+                (root, config)
+            })
+            .collect();
+        // Import targets are picked out of the walked entries, so a target
+        // no walk reaches (e.g. an alias into a directory above cwd) is not
+        // used rather than silently lost
+        let mut imports = match imports::looks_like_import(&parsed.pattern) {
+            true => imports::resolve(&parsed.pattern, &parsed.search_base),
+            false => Vec::new(),
+        };
+        imports.retain(|file| {
+            reaches(&parsed.search_base, &walk_config, file)
+                || roots
+                    .iter()
+                    .any(|(root, config)| reaches(&root.dir, config, file))
+        });
+
+        Some(Plan {
+            include_ignored: self.include_ignored || parsed.include_ignored,
+            parsed,
+            cwd_in_root,
+            walk_config,
+            roots,
+            imports,
+        })
+    }
+
+    /// Configure walk depth based on whether we have a pattern, plus
+    /// `extra_depth` levels, keeping walks from `/` or the home directory
+    /// shallow.
+    fn walk_config(&self, base: &Path, is_empty: bool, extra_depth: usize) -> WalkConfig {
+        let mut walk_config = if is_empty {
+            walker::SHALLOW_CONFIG
+        } else {
+            walker::DEEP_CONFIG
+        }
+        .symlinks(self.symlinks)
+        .submodules(self.submodules);
+        if let Some(max_depth) = self.max_depth {
+            walk_config.max_depth = max_depth;
+        }
+        walk_config.max_depth += extra_depth;
+        if is_broad(base, config::home_dir().as_deref()) {
+            walk_config = walk_config
+                .max_depth(walk_config.max_depth.min(self.limits.broad_max_depth))
+                .same_file_system(self.limits.same_file_system)
+                .max_entries(Some(self.limits.max_entries));
+        }
+        walk_config
+    }
+}
+
+/// Where and how a search walks, derived from the query and settings.
+pub(crate) struct Plan {
+    pub parsed: ParsedQuery,
+    /// Working directory relative to the project root, when searching from it
+    pub cwd_in_root: Option<String>,
+    pub walk_config: WalkConfig,
+    pub include_ignored: bool,
+    /// Additional roots walked alongside `parsed.search_base`
+    pub roots: Vec<(Root, WalkConfig)>,
+    /// Files the query resolves to as an import path, most specific first
+    pub imports: Vec<PathBuf>,
+}
+
+/// A walked entry and the path it is shown as.
+struct Candidate {
+    entry: Entry,
+    shown: String,
+    /// Exact form of `shown` for paths that are not valid UTF-8
+    raw: Option<PathBuf>,
+    /// Position in the plan's import targets, if the entry is one
+    import: Option<usize>,
+    /// Trailing path segments shared with a pasted path
+    suffix: usize,
+}
+
+impl Candidate {
+    /// The path matched against, relative to the walked directory
+    fn path(&self) -> &str {
+        &self.entry.path
+    }
+}
+
+/// How many trailing segments `path` shares with `pasted`, e.g. 2 for
+/// "src/x.rs" and "home/ci/repo/src/x.rs".
+fn shared_suffix(path: &str, pasted: &str) -> usize {
+    path.rsplit('/')
+        .zip(pasted.rsplit('/'))
+        .take_while(|(a, b)| a == b)
+        .count()
+}
+
+/// Whether a walk of `base` with `config` lists `file`.
+fn reaches(base: &Path, config: &WalkConfig, file: &Path) -> bool {
+    file.strip_prefix(base)
+        .is_ok_and(|rel| rel.components().count() <= config.max_depth)
+}
+
+/// Whether the last segment of `path` has an extension or is a dot file,
+/// e.g. "x.rs" or ".env", unlike "repo".
+fn looks_like_file(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rfind('.').is_some_and(|dot| dot + 1 < name.len())
+}
+
+/// Whether walking `base` would cover far more than a project: the
+/// filesystem root, the home directory or one of its ancestors.
+fn is_broad(base: &Path, home: Option<&Path>) -> bool {
+    base.has_root() && (base.parent().is_none() || home.is_some_and(|home| home.starts_with(base)))
+}
+
+/// The path shown for a walked path, `None` if it should not be suggested.
+pub(crate) fn display_path(
+    parsed: &ParsedQuery,
+    cwd_in_root: Option<&str>,
+    walked: &str,
+) -> Option<String> {
+    match cwd_in_root {
+        Some(cwd_rel) => project::relative_to(cwd_rel, walked),
+        None => Some(format!("{}{}", parsed.output_prefix, walked)),
+    }
+}
+
+/// The exact form of a displayed path whose walked path is not valid UTF-8:
+/// the displayed components that are not part of the walked path (a "../"
+/// or root prefix), followed by the raw components both share.
+fn raw_display(shown: &str, walked: &str, raw: &Path) -> PathBuf {
+    let shown_parts: Vec<&str> = shown.split('/').collect();
+    let shared = shown
+        .split('/')
+        .rev()
+        .zip(walked.split('/').rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let raw_parts: Vec<_> = raw.components().collect();
+
+    let mut out = OsString::new();
+    for part in &shown_parts[..shown_parts.len() - shared] {
+        out.push(part);
+        out.push("/");
+    }
+    for (i, part) in raw_parts[raw_parts.len() - shared..].iter().enumerate() {
+        if i > 0 {
+            out.push("/");
+        }
+        out.push(part.as_os_str());
+    }
+    PathBuf::from(out)
+}
+
+/// Re-index match positions from the walked path into the displayed path.
+/// Both share a suffix; positions outside it (in a dropped prefix) are lost.
+fn rebase_positions(positions: &[u32], matched: &str, display: &str) -> Vec<u32> {
+    let matched_len = matched.chars().count() as u32;
+    let display_len = display.chars().count() as u32;
+    let shared = matched
+        .chars()
+        .rev()
+        .zip(display.chars().rev())
+        .take_while(|(a, b)| a == b)
+        .count() as u32;
+    let start = matched_len - shared;
+
+    positions
+        .iter()
+        .filter(|&&pos| pos >= start)
+        .map(|&pos| pos - start + (display_len - shared))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rebase_positions_with_prefix() {
+        assert_eq!(
+            rebase_positions(&[0, 4], "src/main.rs", "../src/main.rs"),
+            vec![3, 7]
+        );
+    }
+
+    #[test]
+    fn test_rebase_positions_dropped_prefix() {
+        // "src/api/handler.rs" shown as "handler.rs" from cwd src/api
+        assert_eq!(
+            rebase_positions(&[0, 8, 9], "src/api/handler.rs", "handler.rs"),
+            vec![0, 1]
+        );
+        // "src/lib.rs" shown as "../lib.rs" from cwd src/api
+        assert_eq!(
+            rebase_positions(&[0, 4], "src/lib.rs", "../lib.rs"),
+            vec![3]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_raw_display() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let raw = Path::new(OsStr::from_bytes(b"src/\xff/a.rs"));
+        let shown = |shown, walked| raw_display(shown, walked, raw);
+        assert_eq!(shown("src/\u{fffd}/a.rs", "src/\u{fffd}/a.rs"), raw);
+        assert_eq!(
+            shown("../src/\u{fffd}/a.rs", "src/\u{fffd}/a.rs"),
+            Path::new(OsStr::from_bytes(b"../src/\xff/a.rs"))
+        );
+        // From cwd src, the shared "src" component is dropped
+        assert_eq!(
+            shown("\u{fffd}/a.rs", "src/\u{fffd}/a.rs"),
+            Path::new(OsStr::from_bytes(b"\xff/a.rs"))
+        );
+    }
+
+    #[test]
+    fn test_is_broad() {
+        let home = Some(Path::new("/home/user"));
+        assert!(is_broad(Path::new("/"), home));
+        assert!(is_broad(Path::new("/home"), home));
+        assert!(is_broad(Path::new("/home/user"), home));
+        assert!(!is_broad(Path::new("/home/user/project"), home));
+        assert!(!is_broad(Path::new("/srv"), home));
+        assert!(is_broad(Path::new("/"), None));
+        assert!(!is_broad(Path::new("project"), home));
+    }
+
+    #[test]
+    fn test_imports_outside_the_walk_are_dropped() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let base = dir.path();
+        std::fs::create_dir_all(base.join(".git")).unwrap();
+        std::fs::create_dir_all(base.join("app/src")).unwrap();
+        std::fs::create_dir_all(base.join("shared")).unwrap();
+        std::fs::write(
+            base.join("tsconfig.json"),
+            r#"{"compilerOptions": {"paths": {"@app/*": ["app/src/*"], "@shared/*": ["shared/*"]}}}"#,
+        )
+        .unwrap();
+        std::fs::write(base.join("app/src/x.ts"), "").unwrap();
+        std::fs::write(base.join("shared/y.ts"), "").unwrap();
+
+        let plan = |query: &str| Search::new(base.join("app")).query(query).plan().unwrap();
+        assert_eq!(plan("@app/x").imports, vec![base.join("app/src/x.ts")]);
+        // Above the working directory, so the walk never lists it
+        assert!(plan("@shared/y").imports.is_empty());
+        let plan = Search::new(base.join("app"))
+            .search_root(SearchRoot::Project)
+            .query("@shared/y")
+            .plan()
+            .unwrap();
+        assert_eq!(plan.imports, vec![base.join("shared/y.ts")]);
+    }
+
+    #[test]
+    fn test_looks_like_file() {
+        assert!(looks_like_file("src/x.rs"));
+        assert!(looks_like_file("org/.env"));
+        assert!(!looks_like_file("org/repo"));
+        assert!(!looks_like_file("org/repo."));
+    }
+
+    #[test]
+    fn test_broad_walks_are_shallow() {
+        let plan = Search::new("/").query("hosts").plan().unwrap();
+        assert_eq!(plan.walk_config.max_depth, 2);
+        assert!(plan.walk_config.same_file_system);
+        assert_eq!(plan.walk_config.max_entries, Some(200_000));
+
+        // Project walks are not capped or kept to one filesystem
+        let dir = tempfile::TempDir::new().unwrap();
+        let plan = Search::new(dir.path()).query("hosts").plan().unwrap();
+        assert!(!plan.walk_config.same_file_system);
+        assert_eq!(plan.walk_config.max_entries, None);
+
+        let limits = Limits {
+            broad_max_depth: 4,
+            ..Limits::default()
+        };
+        let plan = Search::new("/")
+            .query("hosts")
+            .limits(limits)
+            .plan()
+            .unwrap();
+        assert_eq!(plan.walk_config.max_depth, 4);
+        // The empty listing is already shallower than the cap
+        let plan = Search::new("/").limits(limits).plan().unwrap();
+        assert_eq!(plan.walk_config.max_depth, 2);
+    }
+
+    #[test]
+    fn test_rebase_positions_unchanged() {
+        assert_eq!(rebase_positions(&[1, 2], "main.rs", "main.rs"), vec![1, 2]);
+    }
+}
@@ -0,0 +1,133 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Deserialize;
+
+/// Secret-like files, as gitignore patterns matched against paths relative
+/// to the search base. Patterns without a `/` match the file name anywhere.
+pub const DEFAULT_SENSITIVE_PATTERNS: &[&str] = &[
+    ".env",
+    ".env.*",
+    "!.env.example",
+    "!.env.sample",
+    "!.env.template",
+    "*.pem",
+    "*.key",
+    "id_rsa*",
+    "id_dsa*",
+    "id_ecdsa*",
+    "id_ed25519*",
+    "!id_*.pub",
+    "*.keystore",
+    "*.jks",
+    "*.p12",
+    "*.pfx",
+    "credentials.json",
+    "*-credentials.json",
+    "service-account*.json",
+    ".netrc",
+    ".pgpass",
+];
+
+/// What happens to files matching the sensitive patterns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SensitivePolicy {
+    /// Never suggest them
+    #[default]
+    Hide,
+    /// Suggest them after every other result, marked as sensitive
+    Flag,
+    /// Treat them like any other file
+    Show,
+}
+
+/// A compiled set of sensitive-file patterns.
+#[derive(Debug, Clone)]
+pub struct SensitiveFiles {
+    matcher: Gitignore,
+}
+
+impl Default for SensitiveFiles {
+    fn default() -> Self {
+        Self::new(DEFAULT_SENSITIVE_PATTERNS)
+    }
+}
+
+impl SensitiveFiles {
+    /// Compile gitignore-style patterns; invalid ones are skipped.
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let mut builder = GitignoreBuilder::new("");
+        for pattern in patterns {
+            let _ = builder.add_line(None, pattern.as_ref());
+        }
+        Self {
+            matcher: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+        }
+    }
+
+    /// The pattern a relative path (with forward slashes) or one of its
+    /// parent directories matches, if any.
+    pub fn matched(&self, path: &str, is_dir: bool) -> Option<&str> {
+        // A sensitive directory hides everything below it
+        match self.matcher.matched_path_or_any_parents(path, is_dir) {
+            ignore::Match::Ignore(glob) => Some(glob.original()),
+            _ => None,
+        }
+    }
+
+    pub fn is_sensitive(&self, path: &str, is_dir: bool) -> bool {
+        self.matched(path, is_dir).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_patterns() {
+        let files = SensitiveFiles::default();
+        for path in [
+            ".env",
+            ".env.local",
+            "config/.env.production",
+            "certs/server.pem",
+            "tls.key",
+            ".ssh/id_rsa",
+            "id_ed25519",
+            "android/release.keystore",
+            "gcp/credentials.json",
+            "deploy/aws-credentials.json",
+            "service-account-prod.json",
+        ] {
+            assert!(files.is_sensitive(path, false), "{path}");
+        }
+        for path in [
+            ".env.example",
+            "id_rsa.pub",
+            "src/main.rs",
+            "environment.ts",
+            "keys.rs",
+            "package.json",
+        ] {
+            assert!(!files.is_sensitive(path, false), "{path}");
+        }
+    }
+
+    #[test]
+    fn test_custom_patterns_replace_defaults() {
+        let files = SensitiveFiles::new(&["secrets/", "*.sops.yaml"]);
+        assert_eq!(files.matched("secrets", true), Some("secrets/"));
+        assert_eq!(files.matched("secrets/db.txt", false), Some("secrets/"));
+        assert_eq!(files.matched("app.sops.yaml", false), Some("*.sops.yaml"));
+        assert!(!files.is_sensitive(".env", false));
+        assert!(!SensitiveFiles::new::<&str>(&[]).is_sensitive(".env", false));
+    }
+
+    #[test]
+    fn test_policy_names() {
+        let policy: SensitivePolicy = serde_json::from_str(r#""flag""#).unwrap();
+        assert_eq!(policy, SensitivePolicy::Flag);
+        assert_eq!(SensitivePolicy::default(), SensitivePolicy::Hide);
+    }
+}
@@ -0,0 +1,349 @@
+use crate::config::home_dir;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable Claude Code uses to relocate `~/.claude`
+const CLAUDE_CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// Claude Code's user settings file: `$CLAUDE_CONFIG_DIR/settings.json`,
+/// or `~/.claude/settings.json`.
+pub fn user_settings_path() -> Option<PathBuf> {
+    let dir = env::var_os(CLAUDE_CONFIG_DIR_ENV)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".claude")))?;
+    Some(dir.join("settings.json"))
+}
+
+/// Claude Code's shared project settings file, `<project>/.claude/settings.json`.
+pub fn project_settings_path(project: &Path) -> PathBuf {
+    project.join(".claude").join("settings.json")
+}
+
+/// Claude Code's personal project settings file, `<project>/.claude/settings.local.json`.
+pub fn local_settings_path(project: &Path) -> PathBuf {
+    project.join(".claude").join("settings.local.json")
+}
+
+/// The parts of a Claude Code settings file that hide paths.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Settings {
+    permissions: Permissions,
+    /// Older form of `Read(...)` deny rules: gitignore patterns for the project
+    ignore_patterns: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Permissions {
+    deny: Vec<String>,
+}
+
+/// Paths Claude Code may not read: the `Read(...)` rules in
+/// `permissions.deny` and the `ignorePatterns` of the user, project and local
+/// settings files. Deny rules always win in Claude Code, so the rules of all
+/// three files apply together: `allow` entries are not read, and negated
+/// patterns such as `Read(!keep.pem)` are dropped rather than lifting a rule.
+#[derive(Debug, Default)]
+pub struct DenyRules {
+    rules: Vec<DenyRule>,
+}
+
+/// One deny rule and where it came from.
+#[derive(Debug)]
+pub struct DenyRule {
+    /// The settings file holding the rule
+    pub file: PathBuf,
+    /// The rule as written, e.g. `Read(./secrets/**)`
+    pub rule: String,
+    /// Directory the pattern is relative to
+    root: PathBuf,
+    matcher: Gitignore,
+}
+
+impl DenyRules {
+    /// Load the rules that apply to a search from `cwd`: the user settings and
+    /// the project settings in the nearest `.claude` directory above `cwd`.
+    /// Missing or malformed files contribute no rules.
+    pub fn load(cwd: &Path) -> Self {
+        Self::from_files(user_settings_path().as_deref(), cwd)
+    }
+
+    fn from_files(user_settings: Option<&Path>, cwd: &Path) -> Self {
+        // Claude Code resolves relative rules against the project it runs in
+        let project = find_project(cwd, user_settings);
+        let project_dir = project.as_deref().unwrap_or(cwd);
+        let files = [
+            user_settings.map(Path::to_path_buf),
+            project.as_deref().map(project_settings_path),
+            project.as_deref().map(local_settings_path),
+        ];
+
+        let mut rules = DenyRules::default();
+        for file in files.into_iter().flatten() {
+            let Some(settings) = fs::read_to_string(&file)
+                .ok()
+                .and_then(|contents| serde_json::from_str::<Settings>(&contents).ok())
+            else {
+                continue;
+            };
+            let settings_dir = settings_base(&file);
+            for rule in settings.permissions.deny {
+                // A bare `Read` denies every file, like `Read(**)`
+                let spec = match rule.strip_prefix("Read(").and_then(|r| r.strip_suffix(')')) {
+                    Some(spec) => spec,
+                    None if rule == "Read" => "**",
+                    None => continue,
+                };
+                let (root, pattern) = resolve_spec(spec, project_dir, &settings_dir);
+                rules.push(&file, &rule, root, &pattern);
+            }
+            for pattern in settings.ignore_patterns {
+                rules.push(&file, &pattern, project_dir.to_path_buf(), &pattern);
+            }
+        }
+        rules
+    }
+
+    fn push(&mut self, file: &Path, rule: &str, root: PathBuf, pattern: &str) {
+        // Negations cannot lift a deny rule, so they are dropped
+        if pattern.is_empty() || pattern.starts_with('!') {
+            return;
+        }
+        let mut builder = GitignoreBuilder::new(&root);
+        let Ok(matcher) = builder
+            .add_line(Some(file.to_path_buf()), pattern)
+            .and_then(|builder| builder.build())
+        else {
+            return;
+        };
+        self.rules.push(DenyRule {
+            file: file.to_path_buf(),
+            rule: rule.to_string(),
+            root,
+            matcher,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The first rule hiding `path` (or one of its parent directories).
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<&DenyRule> {
+        self.rules.iter().find(|rule| {
+            path.strip_prefix(&rule.root).is_ok_and(|inner| {
+                !inner.as_os_str().is_empty()
+                    && rule
+                        .matcher
+                        .matched_path_or_any_parents(inner, is_dir)
+                        .is_ignore()
+            })
+        })
+    }
+}
+
+/// The nearest directory at or above `cwd` with Claude Code project settings,
+/// skipping the user settings directory (`~/.claude` looks like one).
+fn find_project(cwd: &Path, user_settings: Option<&Path>) -> Option<PathBuf> {
+    cwd.ancestors()
+        .find(|dir| {
+            let shared = project_settings_path(dir);
+            (Some(shared.as_path()) != user_settings && shared.is_file())
+                || local_settings_path(dir).is_file()
+        })
+        .map(Path::to_path_buf)
+}
+
+/// The directory `/p` rules in a settings file are relative to: the one
+/// holding its `.claude` directory (the project, or home for the user
+/// settings), or the file's own directory elsewhere (`$CLAUDE_CONFIG_DIR`).
+fn settings_base(file: &Path) -> PathBuf {
+    let dir = file.parent().unwrap_or(Path::new(""));
+    match dir.parent() {
+        Some(parent) if dir.file_name().is_some_and(|name| name == ".claude") => {
+            parent.to_path_buf()
+        }
+        _ => dir.to_path_buf(),
+    }
+}
+
+/// Split a `Read(...)` path into the directory it is relative to and a
+/// gitignore pattern: `//abs` is absolute, `~/p` is in the home directory,
+/// `/p` is relative to the settings file (`settings_dir`), `./p` is
+/// anchored at the project and `p` matches anywhere in it.
+fn resolve_spec(spec: &str, project: &Path, settings_dir: &Path) -> (PathBuf, String) {
+    if let Some(absolute) = spec.strip_prefix("//") {
+        return (PathBuf::from("/"), format!("/{absolute}"));
+    }
+    if let Some(rest) = spec.strip_prefix("~/")
+        && let Some(home) = home_dir()
+    {
+        return (home, format!("/{rest}"));
+    }
+    if spec.starts_with('/') {
+        return (settings_dir.to_path_buf(), spec.to_string());
+    }
+    let pattern = match spec.strip_prefix("./") {
+        Some(rest) => format!("/{rest}"),
+        None => spec.to_string(),
+    };
+    (project.to_path_buf(), pattern)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// A project with the user, project and local files from
+    /// tests/fixtures/permissions, and files for their rules to hide.
+    fn fixture() -> (TempDir, PathBuf, PathBuf) {
+        let dir = TempDir::new().unwrap();
+        let source = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/permissions");
+        let user = dir.path().join("home/.claude/settings.json");
+        let project = dir.path().join("project");
+        fs::create_dir_all(user.parent().unwrap()).unwrap();
+        fs::create_dir_all(project.join(".claude")).unwrap();
+        fs::copy(source.join("user.json"), &user).unwrap();
+        fs::copy(source.join("project.json"), project_settings_path(&project)).unwrap();
+        fs::copy(source.join("local.json"), local_settings_path(&project)).unwrap();
+        (dir, user, project)
+    }
+
+    fn denied_by(rules: &DenyRules, path: &Path) -> Option<String> {
+        rules.matched(path, false).map(|rule| rule.rule.clone())
+    }
+
+    #[test]
+    fn test_rules_from_all_files_apply() {
+        let (_dir, user, project) = fixture();
+        let rules = DenyRules::from_files(Some(&user), &project.join("src"));
+
+        // user.json: anywhere in the project
+        assert_eq!(
+            denied_by(&rules, &project.join("src/app.pem")).as_deref(),
+            Some("Read(*.pem)")
+        );
+        // project.json: anchored at the project root
+        assert_eq!(
+            denied_by(&rules, &project.join(".env")).as_deref(),
+            Some("Read(./.env)")
+        );
+        assert_eq!(denied_by(&rules, &project.join("src/.env")), None);
+        assert!(denied_by(&rules, &project.join("secrets/prod/key.txt")).is_some());
+        // local.json: legacy ignorePatterns
+        assert_eq!(
+            denied_by(&rules, &project.join("scratch/notes.md")).as_deref(),
+            Some("scratch/")
+        );
+        assert_eq!(denied_by(&rules, &project.join("src/main.rs")), None);
+    }
+
+    #[test]
+    fn test_allow_rules_do_not_lift_denies() {
+        // local.json allows Read(./.env) and negates *.pem; both stay denied
+        let (_dir, user, project) = fixture();
+        let rules = DenyRules::from_files(Some(&user), &project);
+        let rule = rules.matched(&project.join(".env"), false).unwrap();
+        assert_eq!(rule.file, project_settings_path(&project));
+        assert!(rules.matched(&project.join("keep.pem"), false).is_some());
+    }
+
+    #[test]
+    fn test_nearest_project_settings() {
+        let (_dir, user, project) = fixture();
+        // A nested project with its own (empty) settings shadows the outer one
+        let nested = project.join("packages/web");
+        fs::create_dir_all(nested.join(".claude")).unwrap();
+        fs::write(project_settings_path(&nested), "{}").unwrap();
+        let rules = DenyRules::from_files(Some(&user), &nested);
+        assert!(rules.matched(&nested.join(".env"), false).is_none());
+        assert!(rules.matched(&nested.join("a.pem"), false).is_some());
+
+        // Without project settings only the user rules apply, relative to cwd
+        let rules = DenyRules::from_files(Some(&user), user.parent().unwrap().parent().unwrap());
+        assert_eq!(rules.rules.len(), 1);
+    }
+
+    #[test]
+    fn test_deny_all_reads() {
+        let (_dir, _user, project) = fixture();
+        for rule in ["Read", "Read(*)", "Read(**)"] {
+            let settings = format!(r#"{{"permissions": {{"deny": ["{rule}"]}}}}"#);
+            fs::write(project_settings_path(&project), settings).unwrap();
+            let rules = DenyRules::from_files(None, &project);
+            for path in ["README.md", "src/main.rs", "docs/guide"] {
+                let matched = rules.matched(&project.join(path), path == "docs/guide");
+                assert_eq!(
+                    matched.map(|r| r.rule.as_str()),
+                    Some(rule),
+                    "{rule} {path}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_malformed_settings_are_skipped() {
+        let (_dir, user, project) = fixture();
+        fs::write(local_settings_path(&project), "{ not json").unwrap();
+        let rules = DenyRules::from_files(Some(&user), &project);
+        assert!(rules.matched(&project.join(".env"), false).is_some());
+        assert!(
+            rules
+                .matched(&project.join("scratch/notes.md"), false)
+                .is_none()
+        );
+
+        let rules = DenyRules::from_files(None, Path::new("/nonexistent"));
+        assert!(rules.is_empty());
+    }
+
+    #[test]
+    fn test_resolve_spec() {
+        let project = Path::new("/work/project");
+        let home = Path::new("/home/me");
+        assert_eq!(
+            resolve_spec("//etc/passwd", project, project),
+            (PathBuf::from("/"), "/etc/passwd".to_string())
+        );
+        assert_eq!(
+            resolve_spec("./secrets/**", project, home),
+            (project.to_path_buf(), "/secrets/**".to_string())
+        );
+        assert_eq!(
+            resolve_spec("/build", project, project),
+            (project.to_path_buf(), "/build".to_string())
+        );
+        // From the user settings, /p is below the home directory
+        assert_eq!(
+            resolve_spec("/build/**", project, home),
+            (home.to_path_buf(), "/build/**".to_string())
+        );
+        assert_eq!(
+            resolve_spec("*.key", project, home),
+            (project.to_path_buf(), "*.key".to_string())
+        );
+    }
+
+    #[test]
+    fn test_settings_base() {
+        assert_eq!(
+            settings_base(Path::new("/home/me/.claude/settings.json")),
+            Path::new("/home/me")
+        );
+        assert_eq!(
+            settings_base(Path::new("/work/project/.claude/settings.local.json")),
+            Path::new("/work/project")
+        );
+        assert_eq!(
+            settings_base(Path::new("/etc/claude/settings.json")),
+            Path::new("/etc/claude")
+        );
+    }
+}
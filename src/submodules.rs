@@ -0,0 +1,168 @@
+use crate::repo::{self, Excludes};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Whether submodules and nested repositories are searched.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SubmodulePolicy {
+    /// Walk into submodules, applying their own ignore rules
+    #[default]
+    Include,
+    /// Skip submodules and nested repositories entirely
+    Exclude,
+}
+
+/// A submodule declared in `.gitmodules`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submodule {
+    /// Name from the `[submodule "name"]` header
+    pub name: String,
+    /// Checkout path relative to the superproject root
+    pub path: String,
+}
+
+impl Submodule {
+    /// Whether a `sub:` token refers to this submodule, by name, path or
+    /// final path component.
+    pub fn matches(&self, token: &str) -> bool {
+        let token = token.trim_end_matches('/');
+        self.name == token || self.path == token || self.path.rsplit('/').next() == Some(token)
+    }
+}
+
+/// Parse the `path` entries of a `.gitmodules` file.
+pub fn parse_gitmodules(contents: &str) -> Vec<Submodule> {
+    let mut submodules = Vec::new();
+    let mut name: Option<String> = None;
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            name = line
+                .strip_prefix("[submodule")
+                .and_then(|rest| rest.trim().strip_suffix(']'))
+                .map(|rest| rest.trim().trim_matches('"').to_string());
+            continue;
+        }
+        let (Some(current), Some((key, value))) = (&name, line.split_once('=')) else {
+            continue;
+        };
+        if key.trim() == "path" {
+            let path = value.trim().trim_matches('"').trim_end_matches('/');
+            submodules.push(Submodule {
+                name: current.clone(),
+                path: path.replace('\\', "/"),
+            });
+        }
+    }
+
+    submodules
+}
+
+/// Nearest directory at or above `start` containing a `.gitmodules` file.
+pub fn find_superproject(start: &Path) -> Option<&Path> {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".gitmodules").is_file())
+}
+
+/// Submodules declared by the superproject at `root`.
+pub fn read_submodules(root: &Path) -> Vec<Submodule> {
+    fs::read_to_string(root.join(".gitmodules"))
+        .map(|contents| parse_gitmodules(&contents))
+        .unwrap_or_default()
+}
+
+/// Resolve a `sub:` token to the submodule directory and the prefix that
+/// makes its paths relative to `cwd`.
+pub fn locate(cwd: &Path, token: &str) -> Option<(PathBuf, String)> {
+    let root = find_superproject(cwd)?;
+    let submodule = read_submodules(root)
+        .into_iter()
+        .find(|s| s.matches(token))?;
+    let depth = cwd.strip_prefix(root).ok()?.components().count();
+    let prefix = format!("{}{}/", "../".repeat(depth), submodule.path);
+    Some((root.join(&submodule.path), prefix))
+}
+
+/// Submodules of the enclosing superproject that live below `base` or
+/// contain it, with their own exclude rules.
+pub fn nested_repos(base: &Path) -> Vec<Excludes> {
+    let Some(root) = find_superproject(base) else {
+        return Vec::new();
+    };
+    read_submodules(root)
+        .into_iter()
+        .map(|submodule| root.join(submodule.path))
+        .filter(|dir| dir.starts_with(base) || base.starts_with(dir))
+        .map(|dir| {
+            // Uninitialized submodules have no git dir and so no rules
+            let git_dir = repo::resolve_git_dir(&dir).unwrap_or_else(|| dir.join(".git"));
+            Excludes::load(&dir, &repo::common_dir(&git_dir))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const GITMODULES: &str = r#"
+[submodule "libfoo"]
+	path = vendor/libfoo
+	url = https://example.com/libfoo.git
+[core]
+	path = not/a/submodule
+[submodule "docs-theme"]
+	path = "themes/docs"
+"#;
+
+    #[test]
+    fn test_parse_gitmodules() {
+        let submodules = parse_gitmodules(GITMODULES);
+        assert_eq!(
+            submodules,
+            vec![
+                Submodule {
+                    name: "libfoo".to_string(),
+                    path: "vendor/libfoo".to_string(),
+                },
+                Submodule {
+                    name: "docs-theme".to_string(),
+                    path: "themes/docs".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_submodule_matches() {
+        let submodule = &parse_gitmodules(GITMODULES)[1];
+        assert!(submodule.matches("docs-theme"));
+        assert!(submodule.matches("themes/docs"));
+        assert!(submodule.matches("docs"));
+        assert!(submodule.matches("docs/"));
+        assert!(!submodule.matches("themes"));
+    }
+
+    #[test]
+    fn test_locate_from_subdirectory() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(root.join(".gitmodules"), GITMODULES).unwrap();
+        fs::create_dir_all(root.join("src/api")).unwrap();
+
+        let (path, prefix) = locate(root, "libfoo").unwrap();
+        assert_eq!(path, root.join("vendor/libfoo"));
+        assert_eq!(prefix, "vendor/libfoo/");
+
+        let (_, prefix) = locate(&root.join("src/api"), "docs").unwrap();
+        assert_eq!(prefix, "../../themes/docs/");
+
+        assert!(locate(root, "missing").is_none());
+    }
+}
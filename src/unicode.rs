@@ -0,0 +1,96 @@
+//! Canonical composition, so file names written decomposed (NFD, as macOS
+//! filesystems store them) match queries typed composed (NFC) and vice versa.
+
+use unicode_normalization::char::canonical_combining_class;
+use unicode_normalization::{IsNormalized, UnicodeNormalization, is_nfc, is_nfc_quick};
+
+/// Compose `text` to NFC. Returns `None` if nothing changes, otherwise the
+/// composed text and, for each of its chars, the index of a char in `text`
+/// it came from (to map match positions back).
+///
+/// The text is normalized in runs that start at a char nothing before it
+/// can combine with, so each composed char is traced to its run: the first
+/// char of a run maps to where the run starts, the rest to the chars after.
+pub(crate) fn compose(text: &str) -> Option<(String, Vec<u32>)> {
+    if text.is_ascii() || is_nfc(text) {
+        return None;
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut composed = String::with_capacity(text.len());
+    let mut starts = Vec::new();
+    let mut run_start = 0;
+    for end in 1..=chars.len() {
+        if end < chars.len() && !is_boundary(chars[end]) {
+            continue;
+        }
+        let run = &chars[run_start..end];
+        for (k, c) in run.iter().copied().nfc().enumerate() {
+            composed.push(c);
+            starts.push((run_start + k.min(run.len() - 1)) as u32);
+        }
+        run_start = end;
+    }
+    Some((composed, starts))
+}
+
+/// Whether `c` starts a new run: it has no combining class and cannot
+/// compose with a char before it.
+fn is_boundary(c: char) -> bool {
+    canonical_combining_class(c) == 0 && is_nfc_quick(std::iter::once(c)) != IsNormalized::Maybe
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nfc(text: &str) -> String {
+        compose(text).map_or_else(|| text.to_string(), |(composed, _)| composed)
+    }
+
+    #[test]
+    fn test_compose_latin() {
+        assert_eq!(nfc("cafe\u{301}.md"), "caf\u{e9}.md");
+        assert_eq!(nfc("A\u{30a}ngstro\u{308}m"), "\u{c5}ngstr\u{f6}m");
+        // Two marks: dot below, then circumflex
+        assert_eq!(nfc("e\u{323}\u{302}"), "\u{1ec7}");
+        assert_eq!(compose("caf\u{e9}.md"), None);
+        assert_eq!(compose("main.rs"), None);
+    }
+
+    #[test]
+    fn test_compose_maps_positions() {
+        let (composed, starts) = compose("e\u{301}cole/re\u{301}sume\u{301}").unwrap();
+        assert_eq!(composed, "\u{e9}cole/r\u{e9}sum\u{e9}");
+        assert_eq!(starts, vec![0, 2, 3, 4, 5, 6, 7, 8, 10, 11, 12, 13]);
+    }
+
+    #[test]
+    fn test_compose_cjk() {
+        // Kana with (han)dakuten, as macOS stores them
+        assert_eq!(nfc("\u{30ac}\u{30a4}\u{30c9}"), "\u{30ac}\u{30a4}\u{30c9}");
+        assert_eq!(
+            nfc("\u{30ab}\u{3099}\u{30a4}\u{30c8}\u{3099}"),
+            "\u{30ac}\u{30a4}\u{30c9}"
+        );
+        assert_eq!(nfc("\u{30cf}\u{309a}\u{30f3}"), "\u{30d1}\u{30f3}");
+        // Hangul jamo, with and without a trailing consonant
+        assert_eq!(
+            nfc("\u{1112}\u{1161}\u{11ab}\u{1100}\u{1173}\u{11af}"),
+            "\u{d55c}\u{ae00}"
+        );
+        assert_eq!(nfc("\u{1100}\u{1161}"), "\u{ac00}");
+        // Han characters have no decomposition
+        assert_eq!(compose("\u{6587}\u{66f8}/\u{8aac}\u{660e}.md"), None);
+    }
+
+    #[test]
+    fn test_compose_reorders_marks() {
+        // Circumflex typed before dot below: canonical order puts the dot
+        // first, and both still compose
+        assert_eq!(nfc("e\u{302}\u{323}"), "\u{1ec7}");
+        // A mark that does not compose does not block a later one
+        assert_eq!(nfc("a\u{316}\u{301}"), "\u{e1}\u{316}");
+        let (_, starts) = compose("xa\u{316}\u{301}y").unwrap();
+        assert_eq!(starts, vec![0, 1, 2, 4]);
+    }
+}
@@ -1,15 +1,160 @@
-use ignore::{DirEntry, WalkBuilder, WalkState};
-use std::path::Path;
+use crate::repo::{self, Excludes};
+use crate::settings::DenyRules;
+use crate::submodules::{self, SubmodulePolicy};
+use ignore::gitignore::{Gitignore, GitignoreBuilder, Glob};
+use ignore::{DirEntry, Match, WalkBuilder, WalkState};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc;
 
+/// How symbolic links are treated during the walk.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum SymlinkPolicy {
+    /// List links but never descend into them
+    #[default]
+    Never,
+    /// Follow links whose target lies inside the search base
+    WithinRoot,
+    /// Follow every link
+    Anywhere,
+}
+
+/// What kind of filesystem object a walked path is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[non_exhaustive]
+pub enum EntryKind {
+    File,
+    Dir,
+    /// A link that was not followed
+    Symlink,
+}
+
+/// A path found by the walk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Entry {
+    /// Path relative to the walk base, with forward slashes. Names that are
+    /// not valid UTF-8 are converted lossily; see `raw_path`.
+    pub path: String,
+    pub kind: EntryKind,
+    /// Whether the path is normally hidden by ignore rules or SKIP_DIRS
+    pub ignored: bool,
+    /// The exact relative path, only when it is not valid UTF-8
+    pub raw_path: Option<PathBuf>,
+}
+
+impl Entry {
+    /// The exact path relative to the walk base.
+    pub fn os_path(&self) -> &Path {
+        self.raw_path
+            .as_deref()
+            .unwrap_or_else(|| Path::new(&self.path))
+    }
+}
+
+/// Walk options. Start from [`SHALLOW_CONFIG`], [`DEEP_CONFIG`] or
+/// `WalkConfig::default()` and adjust with the builder methods.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
 pub struct WalkConfig {
     pub max_depth: usize,
+    pub symlinks: SymlinkPolicy,
+    pub submodules: SubmodulePolicy,
+    /// Do not cross into other mounted filesystems
+    pub same_file_system: bool,
+    /// Stop the walk after this many entries. A capped walk runs on one
+    /// thread in name order, so the entries kept are always the same.
+    pub max_entries: Option<usize>,
+}
+
+impl Default for WalkConfig {
+    fn default() -> Self {
+        DEEP_CONFIG
+    }
+}
+
+impl WalkConfig {
+    pub const fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub const fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    pub const fn submodules(mut self, submodules: SubmodulePolicy) -> Self {
+        self.submodules = submodules;
+        self
+    }
+
+    pub const fn same_file_system(mut self, yes: bool) -> Self {
+        self.same_file_system = yes;
+        self
+    }
+
+    pub const fn max_entries(mut self, max_entries: Option<usize>) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
 }
 
-pub const SHALLOW_CONFIG: WalkConfig = WalkConfig { max_depth: 2 };
-pub const DEEP_CONFIG: WalkConfig = WalkConfig { max_depth: 6 };
+pub const SHALLOW_CONFIG: WalkConfig = WalkConfig {
+    max_depth: 2,
+    symlinks: SymlinkPolicy::Never,
+    submodules: SubmodulePolicy::Include,
+    same_file_system: false,
+    max_entries: None,
+};
+pub const DEEP_CONFIG: WalkConfig = WalkConfig {
+    max_depth: 6,
+    symlinks: SymlinkPolicy::Never,
+    submodules: SubmodulePolicy::Include,
+    same_file_system: false,
+    max_entries: None,
+};
+
+/// Guards against walking far more than a project, applied by [`Search`]
+/// to walks from `/`, the home directory or a directory above it (`limits`
+/// in the config file). Other walks are not capped.
+///
+/// [`Search`]: crate::Search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct Limits {
+    /// Depth limit for broad walks (e.g. after "../../../../")
+    pub broad_max_depth: usize,
+    /// Keep broad walks on the filesystem of the search base
+    pub same_file_system: bool,
+    /// Stop broad walks after this many entries
+    pub max_entries: usize,
+}
 
-const SKIP_DIRS: &[&str] = &[
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            broad_max_depth: 2,
+            same_file_system: true,
+            max_entries: 200_000,
+        }
+    }
+}
+
+/// Kernel and device filesystems that are never walked, even when
+/// `same_file_system` is off
+const PSEUDO_FS_DIRS: &[&str] = &["/proc", "/sys", "/dev", "/run", "/System/Volumes"];
+
+pub(crate) const SKIP_DIRS: &[&str] = &[
     ".git",
     "node_modules",
     ".venv",
@@ -24,9 +169,16 @@ const SKIP_DIRS: &[&str] = &[
     ".pytest_cache",
 ];
 
+/// Directories skipped even when ignored files are requested
+pub(crate) const ALWAYS_SKIP_DIRS: &[&str] = &[".git"];
+
 /// Check if entry should be skipped based on directory name
 #[inline]
-fn should_skip_entry(entry: &DirEntry) -> bool {
+fn should_skip_entry(entry: &DirEntry, skip_dirs: &[&str]) -> bool {
+    // Submodule checkouts have a `.git` file pointing at the real git dir
+    if entry.file_name() == ".git" {
+        return true;
+    }
     let Some(file_type) = entry.file_type() else {
         return false;
     };
@@ -36,13 +188,13 @@ fn should_skip_entry(entry: &DirEntry) -> bool {
     let Some(name) = entry.file_name().to_str() else {
         return false;
     };
-    SKIP_DIRS.contains(&name)
+    skip_dirs.contains(&name)
 }
 
 /// Check if path contains any skip directories
 #[inline]
-fn path_contains_skip_dir(path: &str) -> bool {
-    for skip in SKIP_DIRS {
+fn path_contains_skip_dir(path: &str, skip_dirs: &[&str]) -> bool {
+    for skip in skip_dirs {
         if path.starts_with(skip) && path.as_bytes().get(skip.len()) == Some(&b'/') {
             return true;
         }
@@ -57,61 +209,446 @@ fn path_contains_skip_dir(path: &str) -> bool {
 }
 
 /// Walk files in the given directory using parallel traversal.
-/// Respects .gitignore and skips common directories.
-/// Returns paths relative to the base directory.
+/// Respects .gitignore and Claude Code's `Read(...)` deny rules, and skips
+/// common directories. Returns paths relative to the base directory, lossily
+/// converted if they are not valid UTF-8 ([`walk_entries`] keeps them exact).
 pub fn walk_files(base: &Path, config: &WalkConfig) -> Vec<String> {
+    walk(base, config, true, &DenyRules::load(base))
+        .entries
+        .into_iter()
+        .map(|entry| entry.path)
+        .collect()
+}
+
+/// Walk like [`walk_files`], returning typed entries. With `include_ignored`
+/// the entries hidden by ignore files or `SKIP_DIRS` are added and marked
+/// (`.git` and paths denied by Claude Code settings are still left out).
+pub fn walk_entries(base: &Path, config: &WalkConfig, include_ignored: bool) -> Vec<Entry> {
+    walk_entries_counted(base, config, include_ignored, &DenyRules::load(base)).entries
+}
+
+/// The entries of a walk, and what it could not list.
+#[derive(Debug, Default)]
+pub(crate) struct Walked {
+    pub entries: Vec<Entry>,
+    /// Entries that could not be read (permission errors, symlink loops)
+    pub errors: usize,
+    /// The walk stopped at `max_entries`
+    pub truncated: bool,
+}
+
+/// [`walk_entries`] with the deny rules for the search's working directory,
+/// also counting unreadable entries and noting whether the cap was hit.
+pub(crate) fn walk_entries_counted(
+    base: &Path,
+    config: &WalkConfig,
+    include_ignored: bool,
+    deny: &DenyRules,
+) -> Walked {
+    let mut walked = walk(base, config, true, deny);
+    if include_ignored {
+        let visible: HashSet<PathBuf> = walked
+            .entries
+            .iter()
+            .map(|e| e.os_path().to_owned())
+            .collect();
+        let all = walk(base, config, false, deny);
+        let ignored = all
+            .entries
+            .into_iter()
+            .filter(|e| !visible.contains(e.os_path()))
+            .map(|e| Entry { ignored: true, ..e });
+        walked.entries.extend(ignored);
+        walked.errors = walked.errors.max(all.errors);
+        walked.truncated |= all.truncated;
+    }
+    walked
+}
+
+fn walk(base: &Path, config: &WalkConfig, filtered: bool, deny: &DenyRules) -> Walked {
     let (tx, rx) = mpsc::channel();
+    let errors = AtomicUsize::new(0);
+    let errors = &errors;
+    let truncated = AtomicBool::new(false);
+    let truncated = &truncated;
+    let skip_dirs = if filtered {
+        SKIP_DIRS
+    } else {
+        ALWAYS_SKIP_DIRS
+    };
 
-    let walker = WalkBuilder::new(base)
+    // Canonical base is only needed to resolve links (loops are reported as
+    // errors by `ignore` and skipped below)
+    let follow = config.symlinks != SymlinkPolicy::Never;
+    let canonical_base = if follow {
+        base.canonicalize().ok()
+    } else {
+        None
+    };
+    let canonical_base = canonical_base.as_deref();
+
+    let rules = IgnoreRules::new(base);
+    let excludes = rules.excludes.as_slice();
+    let exclude_nested = config.submodules == SubmodulePolicy::Exclude;
+
+    let entries_left = AtomicUsize::new(config.max_entries.unwrap_or(usize::MAX));
+    let entries_left = &entries_left;
+
+    let mut builder = WalkBuilder::new(base);
+    builder
         .hidden(false)
         .max_depth(Some(config.max_depth))
-        .git_ignore(true)
-        .git_global(true)
-        .git_exclude(true)
-        .threads(
-            std::thread::available_parallelism()
-                .map(|p| p.get())
-                .unwrap_or(4),
-        )
-        .build_parallel();
-
-    walker.run(|| {
-        let tx = tx.clone();
-        Box::new(move |result| {
-            let entry = match result {
-                Ok(e) => e,
-                Err(_) => return WalkState::Continue,
-            };
+        .same_file_system(config.same_file_system)
+        .follow_links(follow)
+        .ignore(filtered)
+        .parents(filtered)
+        .git_ignore(filtered)
+        .git_global(filtered)
+        .git_exclude(filtered)
+        .require_git(rules.require_git);
 
-            // Skip root directory
-            if entry.depth() == 0 {
-                return WalkState::Continue;
-            }
+    // The entry for a walked path, or what the walk does instead of listing it
+    let check = |path: &Path, kind: EntryKind| -> Result<Entry, WalkState> {
+        // Get relative path (always use forward slashes for consistency)
+        let Ok(rel_path) = path.strip_prefix(base) else {
+            return Err(WalkState::Continue);
+        };
+        // Names that are not UTF-8 are matched lossily but kept exactly
+        let lossy = rel_path.to_string_lossy();
+        let raw_path = matches!(lossy, Cow::Owned(_)).then(|| rel_path.to_path_buf());
+        // Normalize to forward slashes (no-op on Unix, converts \ on Windows)
+        let s = lossy.replace('\\', "/");
+        if path_contains_skip_dir(&s, skip_dirs) {
+            return Err(WalkState::Continue);
+        }
 
-            // Skip directories in our skip list (and don't descend into them)
-            if should_skip_entry(&entry) {
-                return WalkState::Skip;
-            }
+        let is_dir = kind == EntryKind::Dir;
+        if is_dir && PSEUDO_FS_DIRS.iter().any(|dir| path == Path::new(dir)) {
+            return Err(WalkState::Skip);
+        }
+        if exclude_nested && is_dir && is_nested_repo(path, excludes) {
+            return Err(WalkState::Skip);
+        }
+        // Paths Claude Code may not read are never suggested, even with !!
+        let denied = !deny.is_empty() && deny.matched(path, is_dir).is_some();
+        if denied || filtered && excludes.iter().any(|e| e.is_excluded(path, is_dir)) {
+            return Err(if is_dir {
+                WalkState::Skip
+            } else {
+                WalkState::Continue
+            });
+        }
 
-            // Get relative path (always use forward slashes for consistency)
-            let Ok(rel_path) = entry.path().strip_prefix(base) else {
-                return WalkState::Continue;
-            };
-            let Some(s) = rel_path.to_str() else {
-                return WalkState::Continue;
-            };
-            // Normalize to forward slashes (no-op on Unix, converts \ on Windows)
-            let s = s.replace('\\', "/");
-            if !path_contains_skip_dir(&s) {
-                let _ = tx.send(s);
+        // Claim a slot before sending, so the cap holds across threads
+        if entries_left
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |left| {
+                left.checked_sub(1)
+            })
+            .is_err()
+        {
+            truncated.store(true, Ordering::Relaxed);
+            return Err(WalkState::Quit);
+        }
+
+        Ok(Entry {
+            path: s,
+            kind,
+            ignored: false,
+            raw_path,
+        })
+    };
+
+    let visit = |result: Result<DirEntry, ignore::Error>, tx: &mpsc::Sender<_>| {
+        let entry = match result {
+            Ok(e) => e,
+            // Following a link whose target is missing fails, but the link
+            // is listed all the same, as when links are not followed
+            Err(err) => {
+                let Some(link) = broken_link(&err) else {
+                    errors.fetch_add(1, Ordering::Relaxed);
+                    return WalkState::Continue;
+                };
+                if link.file_name().is_some_and(|name| name == ".git") {
+                    return WalkState::Continue;
+                }
+                return match check(&link, EntryKind::Symlink) {
+                    Ok(walked) => {
+                        let _ = tx.send((walked, None));
+                        WalkState::Continue
+                    }
+                    Err(state) => state,
+                };
             }
+        };
+
+        // Skip root directory
+        if entry.depth() == 0 {
+            return WalkState::Continue;
+        }
+
+        // Skip directories in our skip list (and don't descend into them)
+        if should_skip_entry(&entry, skip_dirs) {
+            return WalkState::Skip;
+        }
+
+        let kind = match entry.file_type() {
+            Some(ft) if ft.is_dir() => EntryKind::Dir,
+            Some(ft) if ft.is_symlink() => EntryKind::Symlink,
+            _ => EntryKind::File,
+        };
+        let walked = match check(entry.path(), kind) {
+            Ok(walked) => walked,
+            Err(state) => return state,
+        };
 
+        let Some(canonical_base) = canonical_base else {
+            let _ = tx.send((walked, None));
+            return WalkState::Continue;
+        };
+        let Ok(canonical) = entry.path().canonicalize() else {
+            let _ = tx.send((walked, None));
+            return WalkState::Continue;
+        };
+        let Ok(rel_path) = entry.path().strip_prefix(base) else {
+            return WalkState::Continue;
+        };
+        // Links leaving the base are listed but not entered
+        let escapes = config.symlinks == SymlinkPolicy::WithinRoot
+            && entry.path_is_symlink()
+            && !canonical.starts_with(canonical_base);
+        let direct = canonical == canonical_base.join(rel_path);
+        let _ = tx.send((walked, Some(Target { canonical, direct })));
+
+        if escapes {
+            WalkState::Skip
+        } else {
             WalkState::Continue
-        })
-    });
+        }
+    };
+
+    if config.max_entries.is_some() {
+        // Which entries survive a cap must not depend on thread timing, so
+        // capped walks go through the tree in name order on this thread
+        // (`ignore` cannot prune a sequential walk, so the entries below a
+        // skipped directory are passed over instead)
+        let mut skipped: Option<PathBuf> = None;
+        for result in builder.sort_by_file_name(|a, b| a.cmp(b)).build() {
+            let path = match &result {
+                Ok(entry) => Some(Cow::Borrowed(entry.path())),
+                Err(err) => broken_link(err).map(Cow::Owned),
+            };
+            if let (Some(path), Some(dir)) = (path, &skipped)
+                && path.starts_with(dir)
+            {
+                continue;
+            }
+            let dir = result
+                .as_ref()
+                .ok()
+                .filter(|e| e.file_type().is_some_and(|ft| ft.is_dir()))
+                .map(|e| e.path().to_path_buf());
+            match visit(result, &tx) {
+                WalkState::Skip if dir.is_some() => skipped = dir,
+                WalkState::Quit => break,
+                _ => {}
+            }
+        }
+    } else {
+        let threads = std::thread::available_parallelism()
+            .map(|p| p.get())
+            .unwrap_or(4);
+        builder.threads(threads).build_parallel().run(|| {
+            let tx = tx.clone();
+            let visit = &visit;
+            Box::new(move |result| visit(result, &tx))
+        });
+    }
 
     drop(tx); // Close sender so receiver iterator terminates
-    rx.into_iter().collect()
+    Walked {
+        entries: dedupe_targets(rx.into_iter().collect()),
+        errors: errors.load(Ordering::Relaxed),
+        truncated: truncated.load(Ordering::Relaxed),
+    }
+}
+
+/// The ignore rules of a filtered walk from `base`. The walk leaves ignore
+/// files to `ignore`, which keeps its matchers private, so [`Self::ignored`]
+/// rebuilds them with the same options to tell which rule hides a path.
+pub(crate) struct IgnoreRules {
+    base: PathBuf,
+    /// Without it (`GIT_DIR` set) `.gitignore` files count outside a checkout
+    require_git: bool,
+    /// Excludes `ignore` does not find: `ignore` only looks for `.git`
+    /// directories next to the walked files, so linked worktrees, submodules
+    /// and GIT_DIR checkouts get theirs applied by the walk
+    excludes: Vec<Excludes>,
+}
+
+/// The ignore files of one directory.
+struct IgnoreLevel {
+    ignore: Gitignore,
+    git_ignore: Gitignore,
+    git_exclude: Gitignore,
+    has_git: bool,
+}
+
+impl IgnoreRules {
+    pub(crate) fn new(base: &Path) -> Self {
+        // The enclosing repository determines the project root
+        let repository = repo::discover(base);
+        let from_env = repository.as_ref().is_some_and(|r| r.from_env);
+        let mut excludes = submodules::nested_repos(base);
+        excludes.extend(
+            repository
+                .as_ref()
+                .filter(|r| r.work_tree.as_deref().is_some_and(|w| base.starts_with(w)))
+                .and_then(|r| r.excludes()),
+        );
+        Self {
+            base: base.to_path_buf(),
+            require_git: !from_env,
+            excludes,
+        }
+    }
+
+    /// The rule hiding `rel` (relative to the base), or the first directory
+    /// on the way to it, checked top-down as the walk prunes.
+    pub(crate) fn ignored(&self, rel: &Path) -> Option<Glob> {
+        let global = Gitignore::global().0;
+        let mut levels: HashMap<PathBuf, IgnoreLevel> = HashMap::new();
+        let mut path = self.base.clone();
+        let mut components = rel.components().peekable();
+        while let Some(part) = components.next() {
+            for dir in path.ancestors() {
+                if !levels.contains_key(dir) {
+                    levels.insert(dir.to_path_buf(), self.level(dir));
+                }
+            }
+            let dirs: Vec<&IgnoreLevel> = path.ancestors().map(|dir| &levels[dir]).collect();
+            path.push(part);
+            let is_dir = components.peek().is_some() || path.is_dir();
+            let matched = self.matched(&dirs, &global, &path, is_dir).or(self
+                .excludes
+                .iter()
+                .fold(Match::None, |m, e| m.or(e.matched(&path, is_dir))));
+            if let Match::Ignore(glob) = matched {
+                return Some(glob.clone());
+            }
+        }
+        None
+    }
+
+    /// Precedence as in `ignore`: `.ignore` over `.gitignore` over
+    /// `info/exclude` over the global excludes, the deepest file first in
+    /// each; git's files only count inside a repository, up to its root.
+    fn matched<'a>(
+        &self,
+        dirs: &[&'a IgnoreLevel],
+        global: &'a Gitignore,
+        path: &Path,
+        is_dir: bool,
+    ) -> Match<&'a Glob> {
+        let any_git = !self.require_git || dirs.iter().any(|level| level.has_git);
+        let (mut ignore, mut git_ignore, mut git_exclude) = (Match::None, Match::None, Match::None);
+        let mut saw_git = false;
+        for level in dirs {
+            if ignore.is_none() {
+                ignore = level.ignore.matched(path, is_dir);
+            }
+            if any_git && !saw_git && git_ignore.is_none() {
+                git_ignore = level.git_ignore.matched(path, is_dir);
+            }
+            if any_git && !saw_git && git_exclude.is_none() {
+                git_exclude = level.git_exclude.matched(path, is_dir);
+            }
+            saw_git = saw_git || level.has_git;
+        }
+        let global = if any_git {
+            global.matched(path, is_dir)
+        } else {
+            Match::None
+        };
+        ignore.or(git_ignore).or(git_exclude).or(global)
+    }
+
+    fn level(&self, dir: &Path) -> IgnoreLevel {
+        let load = |root: &Path, file: PathBuf| {
+            let mut builder = GitignoreBuilder::new(root);
+            builder.add(file);
+            builder.build().unwrap_or_else(|_| Gitignore::empty())
+        };
+        let git_exclude = match repo::resolve_git_dir(dir) {
+            Some(git_dir) => load(dir, repo::common_dir(&git_dir).join("info/exclude")),
+            None => Gitignore::empty(),
+        };
+        IgnoreLevel {
+            ignore: load(dir, dir.join(".ignore")),
+            git_ignore: load(dir, dir.join(".gitignore")),
+            git_exclude,
+            // Without `require_git` no level ends the search for `.gitignore`s
+            has_git: self.require_git && (dir.join(".git").exists() || dir.join(".jj").exists()),
+        }
+    }
+}
+
+/// The link behind a walk error, if the error is that the link's target
+/// does not exist.
+fn broken_link(err: &ignore::Error) -> Option<PathBuf> {
+    match err {
+        ignore::Error::WithDepth { err, .. } => broken_link(err),
+        ignore::Error::WithPath { path, err }
+            if err
+                .io_error()
+                .is_some_and(|err| err.kind() == io::ErrorKind::NotFound)
+                && fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink()) =>
+        {
+            Some(path.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Whether a directory is a submodule or any other nested git checkout.
+fn is_nested_repo(dir: &Path, repos: &[Excludes]) -> bool {
+    repos.iter().any(|repo| repo.work_tree == dir) || dir.join(".git").exists()
+}
+
+/// Where a walked path really lives (only tracked when following links).
+struct Target {
+    canonical: PathBuf,
+    /// Whether the path reaches its target without going through a link
+    direct: bool,
+}
+
+/// Keep one path per canonical target when following links, preferring the
+/// direct path, then the shortest, then the lexicographically smallest.
+fn dedupe_targets(entries: Vec<(Entry, Option<Target>)>) -> Vec<Entry> {
+    let mut paths = Vec::with_capacity(entries.len());
+    let mut best: HashMap<PathBuf, (Entry, bool)> = HashMap::new();
+
+    for (entry, target) in entries {
+        let Some(Target { canonical, direct }) = target else {
+            paths.push(entry);
+            continue;
+        };
+        let key = |e: &Entry, direct: bool| (!direct, e.path.len(), e.path.clone());
+        match best.get_mut(&canonical) {
+            Some(current) if key(&entry, direct) < key(&current.0, current.1) => {
+                *current = (entry, direct);
+            }
+            Some(_) => {}
+            None => {
+                best.insert(canonical, (entry, direct));
+            }
+        }
+    }
+
+    // In name order, not the map's
+    let start = paths.len();
+    paths.extend(best.into_values().map(|(path, _)| path));
+    paths[start..].sort_by(|a, b| a.path.cmp(&b.path));
+    paths
 }
 
 #[cfg(test)]
@@ -216,6 +753,66 @@ mod tests {
         assert!(deep.iter().any(|p| p.contains("deep.txt")));
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_walk_keeps_non_utf8_names() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = TempDir::new().unwrap();
+        let name = OsStr::from_bytes(b"caf\xe9");
+        fs::create_dir(dir.path().join(name)).unwrap();
+        fs::write(dir.path().join(name).join("menu.txt"), "").unwrap();
+        fs::write(dir.path().join("plain.txt"), "").unwrap();
+
+        let entries = walk_entries(dir.path(), &DEEP_CONFIG, false);
+        let file = entries
+            .iter()
+            .find(|e| e.path.ends_with("menu.txt"))
+            .unwrap();
+        assert_eq!(file.path, "caf\u{fffd}/menu.txt");
+        assert_eq!(file.os_path(), Path::new(name).join("menu.txt"));
+        let plain = entries.iter().find(|e| e.path == "plain.txt").unwrap();
+        assert_eq!(plain.raw_path, None);
+        assert_eq!(walk_files(dir.path(), &DEEP_CONFIG).len(), 3);
+    }
+
+    #[test]
+    fn test_walk_max_entries() {
+        let dir = TempDir::new().unwrap();
+        for i in 0..20 {
+            fs::write(dir.path().join(format!("file{i}.txt")), "").unwrap();
+        }
+
+        let capped = walk_files(dir.path(), &DEEP_CONFIG.max_entries(Some(5)));
+        let mut expected: Vec<String> = (0..20).map(|i| format!("file{i}.txt")).collect();
+        expected.sort();
+        expected.truncate(5);
+        assert_eq!(capped, expected);
+        let config = DEEP_CONFIG.max_entries(Some(5));
+        assert!(walk_entries_counted(dir.path(), &config, false, &DenyRules::default()).truncated);
+        let config = DEEP_CONFIG.max_entries(Some(20));
+        assert!(!walk_entries_counted(dir.path(), &config, false, &DenyRules::default()).truncated);
+
+        // Skipped directories stay skipped in a capped walk
+        fs::create_dir_all(dir.path().join("node_modules/pkg")).unwrap();
+        fs::write(dir.path().join("node_modules/pkg/index.js"), "").unwrap();
+        let capped = walk_files(dir.path(), &DEEP_CONFIG.max_entries(Some(100)));
+        assert_eq!(capped.len(), 20, "{capped:?}");
+        assert_eq!(walk_files(dir.path(), &DEEP_CONFIG).len(), 20);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_walk_skips_pseudo_filesystems() {
+        let config = WalkConfig {
+            max_depth: 1,
+            ..SHALLOW_CONFIG
+        };
+        let entries = walk_entries(Path::new("/"), &config, true);
+        assert!(!entries.iter().any(|e| e.path == "proc" || e.path == "sys"));
+    }
+
     #[test]
     fn test_walk_respects_gitignore() {
         let dir = TempDir::new().unwrap();
@@ -248,37 +845,349 @@ mod tests {
         assert!(paths.iter().any(|p| p == "src/main.rs"));
     }
 
+    #[test]
+    fn test_walk_with_ignored_splits_gitignored() {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path();
+
+        fs::create_dir_all(base.join(".git")).unwrap();
+        fs::write(base.join(".gitignore"), ".env.local\n").unwrap();
+        fs::write(base.join(".env.local"), "").unwrap();
+        fs::write(base.join("main.rs"), "").unwrap();
+
+        let entries = walk_entries(base, &DEEP_CONFIG, true);
+        let find = |path: &str| entries.iter().find(|e| e.path == path).unwrap();
+
+        assert!(!find("main.rs").ignored);
+        assert!(find(".env.local").ignored);
+        assert_eq!(find(".env.local").kind, EntryKind::File);
+        assert!(
+            !walk_entries(base, &DEEP_CONFIG, false)
+                .iter()
+                .any(|e| e.ignored)
+        );
+    }
+
+    #[test]
+    fn test_walk_with_ignored_includes_skip_dirs_but_not_git() {
+        let dir = create_test_tree();
+        fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+        fs::write(dir.path().join("target/debug/app"), "").unwrap();
+
+        let entries = walk_entries(dir.path(), &DEEP_CONFIG, true);
+        let ignored = |path: &str| entries.iter().any(|e| e.path == path && e.ignored);
+
+        assert!(ignored("node_modules"));
+        assert!(ignored("node_modules/pkg/index.js"));
+        assert!(ignored("target/debug/app"));
+        assert!(!entries.iter().any(|e| e.path.contains(".git")));
+        assert!(
+            entries
+                .iter()
+                .any(|e| e.path == "src/main.rs" && !e.ignored)
+        );
+    }
+
+    /// Returns the tree and the external directory `outside` points to.
+    #[cfg(unix)]
+    fn symlink_tree() -> (TempDir, TempDir) {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().unwrap();
+        let base = dir.path();
+
+        // ├── real/lib.rs
+        // ├── linked -> real
+        // ├── real/loop -> ..       (cycle back to base)
+        // └── outside -> <external dir with external.rs>
+        fs::create_dir_all(base.join("real")).unwrap();
+        fs::write(base.join("real/lib.rs"), "").unwrap();
+        symlink(base.join("real"), base.join("linked")).unwrap();
+        symlink("..", base.join("real/loop")).unwrap();
+
+        let external = TempDir::new().unwrap();
+        fs::write(external.path().join("external.rs"), "").unwrap();
+        symlink(external.path(), base.join("outside")).unwrap();
+
+        (dir, external)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_never_followed_by_default() {
+        let (dir, _external) = symlink_tree();
+        let paths = walk_files(dir.path(), &DEEP_CONFIG);
+
+        assert!(paths.iter().any(|p| p == "linked"));
+        assert!(paths.iter().any(|p| p == "outside"));
+        assert!(!paths.iter().any(|p| p.starts_with("linked/")));
+        assert!(!paths.iter().any(|p| p.starts_with("outside/")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_within_root_dedupes_and_stays_inside() {
+        let (dir, _external) = symlink_tree();
+        let config = WalkConfig {
+            symlinks: SymlinkPolicy::WithinRoot,
+            ..DEEP_CONFIG
+        };
+        let paths = walk_files(dir.path(), &config);
+
+        // lib.rs is reachable via real/, linked/ and real/loop/...; listed once
+        let libs: Vec<_> = paths.iter().filter(|p| p.ends_with("lib.rs")).collect();
+        assert_eq!(libs, vec!["real/lib.rs"]);
+        // Outside link is listed but not entered
+        assert!(paths.iter().any(|p| p == "outside"));
+        assert!(!paths.iter().any(|p| p.ends_with("external.rs")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_anywhere_follows_external_links() {
+        let (dir, _external) = symlink_tree();
+        let config = WalkConfig {
+            symlinks: SymlinkPolicy::Anywhere,
+            ..DEEP_CONFIG
+        };
+        let paths = walk_files(dir.path(), &config);
+
+        assert!(paths.iter().any(|p| p == "outside/external.rs"));
+        let libs: Vec<_> = paths.iter().filter(|p| p.ends_with("lib.rs")).collect();
+        assert_eq!(libs, vec!["real/lib.rs"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_self_loop_terminates() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().unwrap();
+        let base = dir.path();
+        fs::create_dir_all(base.join("a/b")).unwrap();
+        fs::write(base.join("a/b/file.txt"), "").unwrap();
+        symlink(base.join("a"), base.join("a/b/up")).unwrap();
+        symlink("self", base.join("self")).unwrap();
+
+        let config = WalkConfig {
+            symlinks: SymlinkPolicy::Anywhere,
+            ..DEEP_CONFIG
+        };
+        let paths = walk_files(base, &config);
+
+        let files: Vec<_> = paths.iter().filter(|p| p.ends_with("file.txt")).collect();
+        assert_eq!(files, vec!["a/b/file.txt"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_symlinks_listed_when_following() {
+        use std::os::unix::fs::symlink;
+
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("file.txt"), "").unwrap();
+        symlink("missing.txt", dir.path().join("dangling")).unwrap();
+
+        for (symlinks, max_entries) in [
+            (SymlinkPolicy::Never, None),
+            (SymlinkPolicy::WithinRoot, None),
+            (SymlinkPolicy::Anywhere, None),
+            (SymlinkPolicy::Anywhere, Some(10)),
+        ] {
+            let config = DEEP_CONFIG.symlinks(symlinks).max_entries(max_entries);
+            let Walked {
+                entries, errors, ..
+            } = walk_entries_counted(dir.path(), &config, false, &DenyRules::default());
+            assert_eq!(errors, 0);
+            let dangling = entries.iter().find(|e| e.path == "dangling");
+            assert_eq!(
+                dangling.map(|e| e.kind),
+                Some(EntryKind::Symlink),
+                "{symlinks:?}: {entries:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dedupe_prefers_direct_then_shortest() {
+        let file = |path: &str| Entry {
+            path: path.to_string(),
+            kind: EntryKind::File,
+            ignored: false,
+            raw_path: None,
+        };
+        let target = |direct| {
+            Some(Target {
+                canonical: PathBuf::from("/repo/real/lib.rs"),
+                direct,
+            })
+        };
+        let paths = |entries| {
+            let mut paths: Vec<String> = dedupe_targets(entries)
+                .into_iter()
+                .map(|e| e.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        let entries = vec![
+            (file("z/lib.rs"), target(false)),
+            (file("real/lib.rs"), target(true)),
+            (file("a/b/lib.rs"), target(false)),
+            (file("plain.rs"), None),
+        ];
+        assert_eq!(paths(entries), vec!["plain.rs", "real/lib.rs"]);
+
+        let entries = vec![
+            (file("zz/lib.rs"), target(false)),
+            (file("a/b/lib.rs"), target(false)),
+            (file("y/lib.rs"), target(false)),
+        ];
+        assert_eq!(paths(entries), vec!["y/lib.rs"]);
+    }
+
+    /// Superproject with a submodule at vendor/libfoo whose git dir lives
+    /// in .git/modules, like `git submodule update` creates.
+    fn create_submodule_tree() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        let base = dir.path();
+
+        fs::create_dir_all(base.join(".git/modules/libfoo/info")).unwrap();
+        fs::create_dir_all(base.join("vendor/libfoo/src")).unwrap();
+        fs::write(
+            base.join(".gitmodules"),
+            "[submodule \"libfoo\"]\n\tpath = vendor/libfoo\n",
+        )
+        .unwrap();
+        fs::write(base.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(
+            base.join("vendor/libfoo/.git"),
+            "gitdir: ../../.git/modules/libfoo\n",
+        )
+        .unwrap();
+        fs::write(base.join(".git/modules/libfoo/info/exclude"), "*.local\n").unwrap();
+        fs::write(base.join("vendor/libfoo/src/lib.rs"), "").unwrap();
+        fs::write(base.join("vendor/libfoo/notes.local"), "").unwrap();
+        fs::write(base.join("vendor/libfoo/debug.log"), "").unwrap();
+        fs::write(base.join("app.log"), "").unwrap();
+        fs::write(base.join("main.rs"), "").unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn test_submodule_own_ignore_rules() {
+        let dir = create_submodule_tree();
+        let paths = walk_files(dir.path(), &DEEP_CONFIG);
+
+        assert!(paths.iter().any(|p| p == "vendor/libfoo/src/lib.rs"));
+        // The submodule's gitlink file is never listed
+        assert!(!paths.iter().any(|p| p.ends_with(".git")));
+        // Its info/exclude applies inside it
+        assert!(!paths.iter().any(|p| p.ends_with("notes.local")));
+        // The superproject's .gitignore does not reach into it (as in git)
+        assert!(paths.iter().any(|p| p == "vendor/libfoo/debug.log"));
+        assert!(!paths.iter().any(|p| p == "app.log"));
+    }
+
+    #[test]
+    fn test_submodule_exclude_policy() {
+        let dir = create_submodule_tree();
+        // A vendored repo that is not a registered submodule
+        fs::create_dir_all(dir.path().join("third_party/zlib/.git")).unwrap();
+        fs::write(dir.path().join("third_party/zlib/zlib.h"), "").unwrap();
+
+        let config = WalkConfig {
+            submodules: SubmodulePolicy::Exclude,
+            ..DEEP_CONFIG
+        };
+        let paths = walk_files(dir.path(), &config);
+
+        assert!(paths.iter().any(|p| p == "main.rs"));
+        assert!(paths.iter().any(|p| p == "third_party"));
+        assert!(!paths.iter().any(|p| p.starts_with("vendor/libfoo")));
+        assert!(!paths.iter().any(|p| p.starts_with("third_party/zlib")));
+    }
+
+    #[test]
+    fn test_submodule_walked_as_base() {
+        let dir = create_submodule_tree();
+        let config = WalkConfig {
+            submodules: SubmodulePolicy::Exclude,
+            ..DEEP_CONFIG
+        };
+        let paths = walk_files(&dir.path().join("vendor/libfoo"), &config);
+
+        assert!(paths.iter().any(|p| p == "src/lib.rs"));
+        assert!(!paths.iter().any(|p| p == ".git"));
+        assert!(!paths.iter().any(|p| p == "notes.local"));
+    }
+
+    #[test]
+    fn test_linked_worktree_uses_common_excludes() {
+        let dir = TempDir::new().unwrap();
+        let main_git = dir.path().join("main/.git");
+        fs::create_dir_all(main_git.join("worktrees/feature")).unwrap();
+        fs::create_dir_all(main_git.join("info")).unwrap();
+        fs::write(main_git.join("info/exclude"), "*.scratch\n").unwrap();
+        fs::write(main_git.join("worktrees/feature/commondir"), "../..\n").unwrap();
+
+        // Relative gitdir, as written by `worktree.useRelativePaths`
+        let worktree = dir.path().join("feature");
+        fs::create_dir_all(worktree.join("src")).unwrap();
+        fs::write(
+            worktree.join(".git"),
+            "gitdir: ../main/.git/worktrees/feature\n",
+        )
+        .unwrap();
+        fs::write(worktree.join(".gitignore"), "*.log\n").unwrap();
+        fs::write(worktree.join("src/lib.rs"), "").unwrap();
+        fs::write(worktree.join("src/notes.scratch"), "").unwrap();
+        fs::write(worktree.join("src/debug.log"), "").unwrap();
+
+        // Walking from a subdirectory still finds the worktree root
+        let paths = walk_files(&worktree.join("src"), &DEEP_CONFIG);
+
+        assert!(paths.iter().any(|p| p == "lib.rs"));
+        assert!(!paths.iter().any(|p| p == "notes.scratch"));
+        assert!(!paths.iter().any(|p| p == "debug.log"));
+    }
+
     // Unit tests for path_contains_skip_dir
     #[test]
     fn test_path_contains_skip_dir_starts_with() {
         // Tests: path.starts_with(skip) && path.as_bytes().get(skip.len()) == Some(&b'/')
-        assert!(path_contains_skip_dir(".git/config"));
-        assert!(path_contains_skip_dir("node_modules/pkg/index.js"));
-        assert!(path_contains_skip_dir("target/debug/binary"));
+        assert!(path_contains_skip_dir(".git/config", SKIP_DIRS));
+        assert!(path_contains_skip_dir(
+            "node_modules/pkg/index.js",
+            SKIP_DIRS
+        ));
+        assert!(path_contains_skip_dir("target/debug/binary", SKIP_DIRS));
     }
 
     #[test]
     fn test_path_contains_skip_dir_middle() {
         // Tests: path.contains(&format!("/{skip}/"))
-        assert!(path_contains_skip_dir("foo/.git/config"));
-        assert!(path_contains_skip_dir("src/node_modules/pkg"));
-        assert!(path_contains_skip_dir("a/b/target/c/d"));
+        assert!(path_contains_skip_dir("foo/.git/config", SKIP_DIRS));
+        assert!(path_contains_skip_dir("src/node_modules/pkg", SKIP_DIRS));
+        assert!(path_contains_skip_dir("a/b/target/c/d", SKIP_DIRS));
     }
 
     #[test]
     fn test_path_contains_skip_dir_exact() {
         // Tests: path == *skip
-        assert!(path_contains_skip_dir(".git"));
-        assert!(path_contains_skip_dir("node_modules"));
-        assert!(path_contains_skip_dir("target"));
+        assert!(path_contains_skip_dir(".git", SKIP_DIRS));
+        assert!(path_contains_skip_dir("node_modules", SKIP_DIRS));
+        assert!(path_contains_skip_dir("target", SKIP_DIRS));
     }
 
     #[test]
     fn test_path_contains_skip_dir_false() {
         // Should not match
-        assert!(!path_contains_skip_dir("src/main.rs"));
-        assert!(!path_contains_skip_dir("gitignore")); // doesn't start with .git/
-        assert!(!path_contains_skip_dir(".github/workflows")); // .github != .git
-        assert!(!path_contains_skip_dir("my_target/foo")); // my_target != target
+        assert!(!path_contains_skip_dir("src/main.rs", SKIP_DIRS));
+        assert!(!path_contains_skip_dir("gitignore", SKIP_DIRS)); // doesn't start with .git/
+        assert!(!path_contains_skip_dir(".github/workflows", SKIP_DIRS)); // .github != .git
+        assert!(!path_contains_skip_dir("my_target/foo", SKIP_DIRS)); // my_target != target
     }
 }
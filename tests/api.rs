@@ -0,0 +1,121 @@
+use claude_search::{
+    DEEP_CONFIG, EntryKind, FuzzyMatcher, Search, SymlinkPolicy, parse_query, walk_entries,
+};
+use std::fs;
+use tempfile::TempDir;
+
+fn create_test_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let base = dir.path();
+
+    fs::create_dir_all(base.join(".git")).unwrap();
+    fs::create_dir_all(base.join("src/nested")).unwrap();
+    fs::write(base.join("src/main.rs"), "fn main() {}").unwrap();
+    fs::write(base.join("src/lib.rs"), "").unwrap();
+    fs::write(base.join("README.md"), "# Test").unwrap();
+
+    dir
+}
+
+#[test]
+fn test_search_returns_typed_results() {
+    let dir = create_test_project();
+    let results = Search::new(dir.path()).query("src/main").run();
+
+    let top = &results[0];
+    assert_eq!(top.path, "src/main.rs");
+    assert_eq!(top.kind, EntryKind::File);
+    assert!(top.score > 0);
+    assert!(!top.ignored);
+    // Every character of the query was matched somewhere in the path
+    assert_eq!(top.positions.len(), "src/main".len());
+    assert!(top.positions.windows(2).all(|w| w[0] < w[1]));
+}
+
+#[test]
+fn test_search_positions_include_prefix() {
+    let dir = create_test_project();
+    let results = Search::new(dir.path().join("src/nested"))
+        .query("../../README")
+        .run();
+
+    let top = &results[0];
+    assert_eq!(top.path, "../../README.md");
+    let highlighted: String = top
+        .positions
+        .iter()
+        .map(|&i| top.path.chars().nth(i as usize).unwrap())
+        .collect();
+    assert_eq!(highlighted, "README");
+}
+
+#[test]
+fn test_search_limit_and_dirs() {
+    let dir = create_test_project();
+    let results = Search::new(dir.path()).limit(1).run();
+    assert_eq!(results.len(), 1);
+
+    let results = Search::new(dir.path()).query("src").run();
+    assert!(
+        results
+            .iter()
+            .any(|r| r.path == "src" && r.kind == EntryKind::Dir)
+    );
+}
+
+#[test]
+fn test_search_missing_directory_is_empty() {
+    let dir = TempDir::new().unwrap();
+    let results = Search::new(dir.path().join("missing")).query("x").run();
+    assert!(results.is_empty());
+}
+
+#[test]
+fn test_lower_level_building_blocks() {
+    let dir = create_test_project();
+    let parsed = parse_query("../main", &dir.path().join("src"));
+    assert_eq!(parsed.pattern, "main");
+    assert_eq!(parsed.output_prefix, "../");
+
+    let config = DEEP_CONFIG.max_depth(1).symlinks(SymlinkPolicy::Never);
+    let entries = walk_entries(dir.path(), &config, false);
+    assert!(entries.iter().any(|e| e.path == "README.md"));
+    assert!(!entries.iter().any(|e| e.path == "src/main.rs"));
+
+    let paths = entries.into_iter().map(|e| e.path).collect();
+    let matched = FuzzyMatcher::new().match_paths(paths, "readme", 5);
+    assert_eq!(matched, vec!["README.md"]);
+}
+
+#[test]
+fn test_search_order_is_deterministic() {
+    // Enough entries that the parallel walk spreads them over its threads
+    let dir = TempDir::new().unwrap();
+    for i in 0..64 {
+        let sub = dir.path().join(format!("dir{i:02}"));
+        fs::create_dir_all(&sub).unwrap();
+        for j in 0..16 {
+            fs::write(sub.join(format!("file{j:02}.rs")), "").unwrap();
+        }
+        #[cfg(unix)]
+        if i < 8 {
+            std::os::unix::fs::symlink(&sub, dir.path().join(format!("link{i}"))).unwrap();
+        }
+    }
+
+    for search in [
+        Search::new(dir.path()),
+        Search::new(dir.path()).symlinks(SymlinkPolicy::Anywhere),
+        Search::new(dir.path()).query("file"),
+    ] {
+        let search = search.limit(usize::MAX);
+        let paths = || -> Vec<String> { search.run().into_iter().map(|r| r.path).collect() };
+        let first = paths();
+        assert!(first.len() >= 64 * 16, "{}", first.len());
+        assert_eq!(first, paths());
+    }
+
+    // The listing without a query is in path order
+    let listing = Search::new(dir.path()).limit(usize::MAX).run();
+    assert!(listing.windows(2).all(|w| w[0].path < w[1].path));
+}
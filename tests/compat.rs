@@ -0,0 +1,196 @@
+//! Parity with `file-suggestion.sh.original`, the shell script this binary
+//! replaces. Each case in tests/fixtures/compat holds the script's output
+//! (fd + fzf, sorted) for one query on the tree listed in tree.txt. The binary
+//! must print the same paths, apart from the intentional differences below.
+//! Order is not compared: fzf and nucleo score paths differently.
+
+use claude_search::SensitiveFiles;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+const GITIGNORE: &str = "*.log\nsecrets/\n";
+
+/// Directories the script's fd command did not exclude in either mode
+const UNEXCLUDED_SKIP_DIRS: &[&str] = &[".tox", ".pytest_cache"];
+
+/// Directories the script only excluded when there was a query
+const QUERY_ONLY_SKIP_DIRS: &[&str] = &[
+    "__pycache__",
+    ".mypy_cache",
+    ".cache",
+    "dist",
+    "build",
+    ".next",
+    "target",
+];
+
+/// Why the binary leaves out a path the script listed, if it does.
+fn intentional_difference(path: &str, empty_query: bool) -> Option<&'static str> {
+    let path = path.trim_start_matches("../");
+    let top = path.split('/').next().unwrap();
+    if path.ends_with(".log") || top == "secrets" {
+        return Some("gitignored, while the script ran fd with --no-ignore-vcs");
+    }
+    if SensitiveFiles::default().is_sensitive(path, false) {
+        return Some("a secret-like file, hidden by the default sensitive-file policy");
+    }
+    if UNEXCLUDED_SKIP_DIRS.contains(&top) {
+        return Some("in a skipped directory the script's fd --exclude list missed");
+    }
+    if empty_query && QUERY_ONLY_SKIP_DIRS.contains(&top) {
+        return Some("in a skipped directory the script only excluded for queries");
+    }
+    None
+}
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/compat")
+        .join(name)
+}
+
+/// Lay out the fixture project described by tree.txt.
+fn create_project() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    let tree = fs::read_to_string(fixture("tree.txt")).unwrap();
+    for path in tree
+        .lines()
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+    {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+    fs::create_dir_all(dir.path().join(".git")).unwrap();
+    fs::write(dir.path().join(".gitignore"), GITIGNORE).unwrap();
+    dir
+}
+
+/// The script's output for one query, with the `# key: value` header.
+struct Golden {
+    query: String,
+    cwd: String,
+    /// Set when the script's `head -50` cut the output short
+    limit: Option<usize>,
+    paths: Vec<String>,
+}
+
+impl Golden {
+    fn load(name: &str) -> Self {
+        let text = fs::read_to_string(fixture(&format!("{name}.txt"))).unwrap();
+        let mut golden = Golden {
+            query: String::new(),
+            cwd: ".".to_string(),
+            limit: None,
+            paths: Vec::new(),
+        };
+        for line in text.lines() {
+            match line.strip_prefix("# ").and_then(|l| l.split_once(':')) {
+                Some(("query", value)) => golden.query = value.trim().to_string(),
+                Some(("cwd", value)) => golden.cwd = value.trim().to_string(),
+                Some(("limit", value)) => golden.limit = Some(value.trim().parse().unwrap()),
+                Some((key, _)) => panic!("unknown header '{key}' in {name}.txt"),
+                None => golden.paths.push(line.to_string()),
+            }
+        }
+        golden
+    }
+}
+
+fn run_claude_search(query: &str, cwd: &Path) -> Vec<String> {
+    let input = serde_json::json!({ "query": query, "cwd": cwd });
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn process");
+    child
+        .stdin
+        .as_mut()
+        .unwrap()
+        .write_all(input.to_string().as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Run the binary on a golden case and compare its paths with the script's.
+fn check(name: &str) {
+    let golden = Golden::load(name);
+    let project = create_project();
+    let actual = run_claude_search(&golden.query, &project.path().join(&golden.cwd));
+    let actual_set: BTreeSet<&str> = actual.iter().map(String::as_str).collect();
+    assert_eq!(actual_set.len(), actual.len(), "{name}: duplicate paths");
+
+    let empty_query = golden.query.trim_start_matches("../").is_empty();
+    let expected: BTreeSet<&str> = golden
+        .paths
+        .iter()
+        .map(String::as_str)
+        .filter(|path| intentional_difference(path, empty_query).is_none())
+        .collect();
+    match golden.limit {
+        // Which paths survive the cap depends on ranking, so only the count
+        // and the candidates are compared
+        Some(limit) => {
+            assert_eq!(actual.len(), limit, "{name}: {actual:?}");
+            assert!(actual_set.is_subset(&expected), "{name}: {actual:?}");
+        }
+        None => assert_eq!(actual_set, expected, "{name}"),
+    }
+
+    for path in &golden.paths {
+        if let Some(reason) = intentional_difference(path, empty_query) {
+            assert!(
+                !actual_set.contains(path.as_str()),
+                "{name}: {path} {reason}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_empty_query_lists_two_levels() {
+    check("empty");
+    check("empty_subdir");
+}
+
+#[test]
+fn test_query_searches_six_levels() {
+    check("depth");
+}
+
+#[test]
+fn test_exclude_list() {
+    check("excludes");
+}
+
+#[test]
+fn test_gitignored_files_are_hidden() {
+    check("gitignored");
+}
+
+#[test]
+fn test_parent_prefix_is_kept() {
+    check("parent");
+    check("parent_twice");
+    check("parent_empty");
+}
+
+#[test]
+fn test_dot_slash_is_stripped() {
+    check("dot_slash");
+}
+
+#[test]
+fn test_output_capped_at_fifty() {
+    check("cap");
+}
@@ -7,8 +7,16 @@ fn run_claude_search(query: &str, cwd: &str) -> String {
     // Escape backslashes for JSON (Windows paths)
     let cwd_escaped = cwd.replace('\\', "\\\\");
     let input = format!(r#"{{"query": "{}", "cwd": "{}"}}"#, query, cwd_escaped);
+    run_claude_search_raw(&input)
+}
+
+fn run_claude_search_raw(input: &str) -> String {
+    run_claude_search_with_env(input, &[])
+}
 
+fn run_claude_search_with_env(input: &str, envs: &[(&str, &std::path::Path)]) -> String {
     let mut child = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+        .envs(envs.iter().copied())
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -265,3 +273,771 @@ fn test_result_limit() {
         lines.len()
     );
 }
+
+#[test]
+fn test_include_ignored_prefix() {
+    let dir = create_test_project();
+    fs::write(dir.path().join(".gitignore"), "env.generated.ts\nbuild/\n").unwrap();
+    fs::write(dir.path().join("env.generated.ts"), "").unwrap();
+    fs::write(dir.path().join("env.rs"), "").unwrap();
+
+    let output = run_claude_search("env", dir.path().to_str().unwrap());
+    assert!(
+        !output.contains("env.generated.ts"),
+        "Ignored file should be hidden without !!: {}",
+        output
+    );
+
+    let output = run_claude_search("!!env", dir.path().to_str().unwrap());
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(
+        lines.contains(&"env.generated.ts"),
+        "!! should include ignored file: {}",
+        output
+    );
+    // Ignored results rank below normal ones
+    let normal = lines.iter().position(|l| *l == "env.rs").unwrap();
+    let ignored = lines.iter().position(|l| *l == "env.generated.ts").unwrap();
+    assert!(
+        normal < ignored,
+        "Ignored result ranked too high: {}",
+        output
+    );
+}
+
+#[test]
+fn test_include_ignored_skip_dirs() {
+    let dir = create_test_project();
+    fs::create_dir_all(dir.path().join("target/debug")).unwrap();
+    fs::write(dir.path().join("target/debug/app.d"), "").unwrap();
+
+    let output = run_claude_search("!!target/debug/app", dir.path().to_str().unwrap());
+    assert!(
+        output.contains("target/debug/app.d"),
+        "!! should include SKIP_DIRS: {}",
+        output
+    );
+}
+
+#[test]
+fn test_json_format_marks_ignored() {
+    let dir = create_test_project();
+    fs::write(dir.path().join(".gitignore"), "secret.txt\n").unwrap();
+    fs::write(dir.path().join("secret.txt"), "").unwrap();
+    fs::write(dir.path().join("secrets.md"), "").unwrap();
+
+    let cwd = dir.path().to_str().unwrap().replace('\\', "\\\\");
+    let input = format!(
+        r#"{{"query": "secret", "cwd": "{}", "include_ignored": true, "format": "json"}}"#,
+        cwd
+    );
+    let output = run_claude_search_raw(&input);
+    let results: Vec<serde_json::Value> = output
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let find = |path: &str| {
+        results
+            .iter()
+            .find(|r| r["path"] == path)
+            .unwrap_or_else(|| panic!("Expected {} in output: {}", path, output))
+    };
+
+    assert_eq!(find("secrets.md")["ignored"], false);
+    assert_eq!(find("secret.txt")["ignored"], true);
+    assert_eq!(find("secret.txt")["kind"], "file");
+    assert!(find("secrets.md")["score"].as_u64().unwrap() > 0);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlink_policy_from_config() {
+    let dir = create_test_project();
+    let vendor = TempDir::new().unwrap();
+    fs::write(vendor.path().join("vendored_pkg.rs"), "").unwrap();
+    std::os::unix::fs::symlink(vendor.path(), dir.path().join("packages")).unwrap();
+
+    let config_dir = TempDir::new().unwrap();
+    let config = config_dir.path().join("config.json");
+    let input = format!(
+        r#"{{"query": "vendored", "cwd": "{}"}}"#,
+        dir.path().to_str().unwrap()
+    );
+
+    fs::write(&config, r#"{"symlinks": "never"}"#).unwrap();
+    let output = run_claude_search_with_env(&input, &[("CLAUDE_SEARCH_CONFIG", &config)]);
+    assert!(
+        !output.contains("vendored_pkg.rs"),
+        "Links should not be followed: {}",
+        output
+    );
+
+    fs::write(&config, r#"{"symlinks": "anywhere"}"#).unwrap();
+    let output = run_claude_search_with_env(&input, &[("CLAUDE_SEARCH_CONFIG", &config)]);
+    assert!(
+        output.contains("packages/vendored_pkg.rs"),
+        "Links should be followed: {}",
+        output
+    );
+}
+
+#[test]
+fn test_submodule_scope_token() {
+    let dir = create_test_project();
+    let base = dir.path();
+    fs::write(
+        base.join(".gitmodules"),
+        "[submodule \"libfoo\"]\n\tpath = vendor/libfoo\n",
+    )
+    .unwrap();
+    fs::create_dir_all(base.join("vendor/libfoo/src")).unwrap();
+    fs::write(
+        base.join("vendor/libfoo/.git"),
+        "gitdir: ../../.git/modules/libfoo\n",
+    )
+    .unwrap();
+    fs::write(base.join("vendor/libfoo/src/lib.rs"), "").unwrap();
+
+    // From a subdirectory, paths stay relative to cwd
+    let output = run_claude_search("sub:libfoo lib", base.join("src").to_str().unwrap());
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(
+        lines.contains(&"../vendor/libfoo/src/lib.rs"),
+        "Expected submodule file relative to cwd: {}",
+        output
+    );
+    assert!(
+        lines.iter().all(|l| l.starts_with("../vendor/libfoo/")),
+        "Superproject files should be out of scope: {}",
+        output
+    );
+    assert!(
+        !output.contains(".git"),
+        "Gitlink file should not be listed: {}",
+        output
+    );
+
+    // Unknown submodules produce nothing
+    let output = run_claude_search("sub:missing lib", base.to_str().unwrap());
+    assert!(output.is_empty(), "Unknown submodule: {}", output);
+}
+
+#[test]
+fn test_git_dir_env_work_tree() {
+    // A checkout whose git dir lives elsewhere, selected via GIT_DIR /
+    // GIT_WORK_TREE, has no .git entry for ignore files to anchor on
+    let store = TempDir::new().unwrap();
+    let git_dir = store.path().join("project.git");
+    fs::create_dir_all(git_dir.join("info")).unwrap();
+    fs::write(git_dir.join("info/exclude"), "*.scratch\n").unwrap();
+
+    let work_tree = TempDir::new().unwrap();
+    fs::write(work_tree.path().join(".gitignore"), "*.log\n").unwrap();
+    fs::write(work_tree.path().join("app.rs"), "").unwrap();
+    fs::write(work_tree.path().join("app.log"), "").unwrap();
+    fs::write(work_tree.path().join("app.scratch"), "").unwrap();
+
+    let input = format!(
+        r#"{{"query": "app", "cwd": "{}"}}"#,
+        work_tree.path().to_str().unwrap().replace('\\', "\\\\")
+    );
+    let output = run_claude_search_with_env(
+        &input,
+        &[("GIT_DIR", &git_dir), ("GIT_WORK_TREE", work_tree.path())],
+    );
+
+    assert!(output.contains("app.rs"), "Expected app.rs: {}", output);
+    assert!(
+        !output.contains("app.log"),
+        "Gitignore should apply: {}",
+        output
+    );
+    assert!(
+        !output.contains("app.scratch"),
+        "GIT_DIR info/exclude should apply: {}",
+        output
+    );
+}
+
+#[test]
+fn test_search_root_project_relative_output() {
+    let dir = create_test_project();
+    let api = dir.path().join("src/api");
+    fs::create_dir_all(&api).unwrap();
+    fs::write(api.join("handler.rs"), "").unwrap();
+
+    let cwd = api.to_str().unwrap().replace('\\', "\\\\");
+    let query = |q: &str| {
+        run_claude_search_raw(&format!(
+            r#"{{"query": "{}", "cwd": "{}", "search_root": "project"}}"#,
+            q, cwd
+        ))
+    };
+
+    // Files outside cwd are reachable without typing ../
+    let output = query("Cargo");
+    assert!(
+        output.lines().any(|l| l == "../../Cargo.toml"),
+        "Expected ../../Cargo.toml: {}",
+        output
+    );
+    let output = query("lib");
+    assert!(
+        output.lines().any(|l| l == "../lib.rs"),
+        "Expected ../lib.rs: {}",
+        output
+    );
+    // Files inside cwd stay plain
+    let output = query("handler");
+    assert!(
+        output.lines().any(|l| l == "handler.rs"),
+        "Expected handler.rs: {}",
+        output
+    );
+    // The walk from the root reaches as deep below cwd as a cwd walk would
+    fs::create_dir_all(api.join("a/b/c/d")).unwrap();
+    fs::write(api.join("a/b/c/d/deep.rs"), "").unwrap();
+    let output = query("deep");
+    assert!(
+        output.lines().any(|l| l == "a/b/c/d/deep.rs"),
+        "Expected a/b/c/d/deep.rs: {}",
+        output
+    );
+
+    // Default still searches cwd only
+    let output = run_claude_search("Cargo", api.to_str().unwrap());
+    assert!(output.is_empty(), "Expected no results: {}", output);
+}
+
+#[test]
+fn test_mcp_server_session() {
+    let dir = create_test_project();
+    let cwd = dir.path().to_str().unwrap().replace('\\', "\\\\");
+    let script = [
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18","capabilities":{},"clientInfo":{"name":"test","version":"0"}}}"#.to_string(),
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#.to_string(),
+        r#"{"jsonrpc":"2.0","id":2,"method":"tools/list"}"#.to_string(),
+        format!(
+            r#"{{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{{"name":"find_files","arguments":{{"query":"src/main","cwd":"{cwd}","limit":5}}}}}}"#
+        ),
+        format!(
+            r#"{{"jsonrpc":"2.0","id":4,"method":"tools/call","params":{{"name":"list_dir","arguments":{{"path":"{cwd}"}}}}}}"#
+        ),
+        r#"{"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"list_dir","arguments":{"path":"/no/such/dir"}}}"#.to_string(),
+    ];
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+        .arg("mcp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn process");
+    let mut stdin = child.stdin.take().unwrap();
+    for line in &script {
+        writeln!(stdin, "{line}").unwrap();
+    }
+    drop(stdin);
+    let output = child.wait_with_output().unwrap();
+
+    let responses: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // One response per request, none for the notification
+    let ids: Vec<u64> = responses
+        .iter()
+        .map(|r| r["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+
+    assert_eq!(responses[0]["result"]["protocolVersion"], "2025-06-18");
+    assert_eq!(responses[1]["result"]["tools"].as_array().unwrap().len(), 2);
+
+    let found = &responses[2]["result"];
+    assert_eq!(found["content"][0]["type"], "text");
+    assert_eq!(
+        found["structuredContent"]["results"][0]["path"],
+        "src/main.rs"
+    );
+
+    let listing = responses[3]["result"]["content"][0]["text"]
+        .as_str()
+        .unwrap();
+    let entries: Vec<&str> = listing.lines().collect();
+    assert!(entries.contains(&"src/"), "{listing}");
+    assert!(entries.contains(&"README.md"), "{listing}");
+    assert!(!entries.contains(&"src/main.rs"), "{listing}");
+
+    assert_eq!(responses[4]["result"]["isError"], true);
+}
+
+#[test]
+fn test_find_subcommand() {
+    let dir = create_test_project();
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+        .args(["find", "src/main", "--limit", "1", "--cwd"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "src/main.rs\n");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+        .args(["find", "main", "--format=json"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    let first: serde_json::Value = serde_json::from_str(
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(first["path"], "src/main.rs");
+
+    // Usage errors go to stderr with a non-zero exit
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+        .args(["find", "--format", "xml"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("--format")
+    );
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_commands_report_errors() {
+    // Unlike the hook, `find` and `doctor` must not fail silently
+    let dir = create_test_project();
+    for command in ["find", "doctor"] {
+        let output = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+            .args([command, "main.rs", "--cwd"])
+            .arg(dir.path())
+            .stdout(fs::File::create("/dev/full").unwrap())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1), "{command}");
+        assert!(
+            String::from_utf8(output.stderr)
+                .unwrap()
+                .starts_with("error: "),
+            "{command}"
+        );
+    }
+}
+
+#[test]
+fn test_json_limit() {
+    let dir = create_test_project();
+    let cwd = dir.path().to_str().unwrap().replace('\\', "\\\\");
+    let output = run_claude_search_raw(&format!(r#"{{"query": "", "cwd": "{cwd}", "limit": 2}}"#));
+    assert_eq!(output.lines().count(), 2, "{output}");
+}
+
+#[test]
+fn test_install_and_uninstall() {
+    let dir = TempDir::new().unwrap();
+    let settings = dir.path().join("settings.json");
+    fs::write(&settings, "{\n  \"model\": \"opus\"\n}\n").unwrap();
+
+    let run = |command: &str| {
+        Command::new(env!("CARGO_BIN_EXE_claude-search"))
+            .arg(command)
+            .env("CLAUDE_CONFIG_DIR", dir.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = run("install");
+    assert!(output.status.success());
+    let installed: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&settings).unwrap()).unwrap();
+    assert_eq!(installed["model"], "opus");
+    assert_eq!(installed["fileSuggestion"]["type"], "command");
+    assert!(
+        installed["fileSuggestion"]["command"]
+            .as_str()
+            .unwrap()
+            .contains("claude-search")
+    );
+    assert!(dir.path().join("settings.json.claude-search.bak").exists());
+
+    let output = run("uninstall");
+    assert!(output.status.success());
+    assert_eq!(
+        fs::read_to_string(&settings).unwrap(),
+        "{\n  \"model\": \"opus\"\n}\n"
+    );
+}
+
+#[test]
+fn test_doctor_subcommand() {
+    let dir = create_test_project();
+    fs::write(dir.path().join(".gitignore"), "*.log\n").unwrap();
+    fs::write(dir.path().join("debug.log"), "").unwrap();
+
+    let doctor = |path: &str| {
+        let output = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+            .args(["doctor", path, "--cwd"])
+            .arg(dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let report = doctor("debug.log");
+    assert!(report.contains("not suggested"), "{report}");
+    assert!(report.contains("`*.log`"), "{report}");
+    assert!(report.contains(".gitignore:1"), "{report}");
+
+    let report = doctor("src/main.rs");
+    assert!(report.contains("rank:  #1"), "{report}");
+    assert!(report.contains("  suggested"), "{report}");
+}
+
+#[test]
+fn test_explain_format() {
+    let dir = create_test_project();
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+        .args(["find", "main", "--format", "explain", "--cwd"])
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    let output = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines[0], "  1. src/[main].rs", "{output}");
+    assert!(lines[1].ends_with("; top result"), "{output}");
+}
+
+#[test]
+fn test_opt_in_log() {
+    let dir = create_test_project();
+    let state = TempDir::new().unwrap();
+    let run = |input: &str| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+            .env("CLAUDE_SEARCH_LOG", "1")
+            .env("XDG_STATE_HOME", state.path())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let cwd = dir.path().to_str().unwrap().replace('\\', "\\\\");
+    let output = run(&format!(r#"{{"query": "main", "cwd": "{cwd}"}}"#));
+    assert!(!output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    // Malformed input still exits 0 with no output, but is logged
+    let output = run("{not json");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+
+    let log = fs::read_to_string(state.path().join("claude-search/claude-search.log")).unwrap();
+    let records: Vec<serde_json::Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["input"]["query"], "main");
+    assert!(records[0]["candidates"].as_u64().unwrap() > 0);
+    assert!(records[0]["walk_ms"].is_number());
+    assert_eq!(records[1]["input"], "{not json");
+    assert!(records[1]["error"].is_string());
+}
+
+#[test]
+fn test_bench_subcommand() {
+    let output = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+        .args(["bench", "--files", "30", "--ignored", "4", "--bulk", "10"])
+        .args(["--runs", "2", "--query", "main", "--query=", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["files"], 30);
+    assert_eq!(report["queries"][0]["query"], "main");
+    assert_eq!(report["queries"][1]["query"], "");
+    for phase in ["walk", "match", "total"] {
+        assert!(report["queries"][0]["phases"][phase]["p50_ms"].is_number());
+    }
+}
+
+#[test]
+fn test_claude_deny_rules_hide_files() {
+    let dir = create_test_project();
+    let user = TempDir::new().unwrap();
+    fs::write(
+        user.path().join("settings.json"),
+        r#"{"permissions": {"deny": ["Read(*.toml)"]}}"#,
+    )
+    .unwrap();
+    fs::create_dir_all(dir.path().join(".claude")).unwrap();
+    fs::write(
+        dir.path().join(".claude/settings.json"),
+        r#"{"permissions": {"deny": ["Read(./.env)", "Read(./secrets/**)"]}}"#,
+    )
+    .unwrap();
+    fs::create_dir_all(dir.path().join("secrets")).unwrap();
+    fs::write(dir.path().join("secrets/prod.key"), "").unwrap();
+    fs::write(dir.path().join(".env"), "").unwrap();
+
+    let search = |query: &str| {
+        let input = serde_json::json!({ "query": query, "cwd": dir.path() }).to_string();
+        run_claude_search_with_env(&input, &[("CLAUDE_CONFIG_DIR", user.path())])
+    };
+    let output = search("");
+    assert!(output.lines().any(|l| l == "README.md"), "{output}");
+    for denied in [".env", "Cargo.toml", "secrets/prod.key"] {
+        assert!(!output.lines().any(|l| l == denied), "{output}");
+        // Not even with !!, which only lifts gitignore rules
+        let output = search(&format!("!!{denied}"));
+        assert!(!output.lines().any(|l| l == denied), "{output}");
+    }
+}
+
+#[test]
+fn test_sensitive_files() {
+    let dir = create_test_project();
+    fs::write(dir.path().join(".gitignore"), "debug.env.log\n").unwrap();
+    fs::write(dir.path().join("debug.env.log"), "").unwrap();
+    fs::write(dir.path().join(".env.local"), "SECRET=1").unwrap();
+    fs::write(dir.path().join(".env.example"), "SECRET=").unwrap();
+    fs::write(dir.path().join("env.rs"), "").unwrap();
+    let search = |extra: &str| {
+        let cwd = serde_json::to_string(dir.path()).unwrap();
+        run_claude_search_raw(&format!(r#"{{"query": "!!env", "cwd": {cwd}{extra}}}"#))
+    };
+
+    // Hidden by default, even with !!; templates are fine
+    let output = search("");
+    assert!(!output.contains(".env.local"), "{output}");
+    assert!(output.lines().any(|l| l == ".env.example"), "{output}");
+
+    // Flagged: after normal and ignored results
+    let output = search(r#", "sensitive": "flag""#);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.last(), Some(&".env.local"), "{output}");
+    assert!(lines.contains(&"debug.env.log"), "{output}");
+
+    let output = search(r#", "sensitive": "flag", "format": "json""#);
+    let last: serde_json::Value = serde_json::from_str(output.lines().last().unwrap()).unwrap();
+    assert_eq!(last["sensitive"], true);
+
+    let output = search(r#", "sensitive": "show""#);
+    let lines: Vec<&str> = output.lines().collect();
+    assert_ne!(lines.last(), Some(&".env.local"), "{output}");
+    assert!(lines.contains(&".env.local"), "{output}");
+}
+
+#[test]
+fn test_additional_roots() {
+    let dir = TempDir::new().unwrap();
+    let base = dir.path();
+    for (path, contents) in [
+        ("web/.git/HEAD", ""),
+        ("web/src/main.ts", ""),
+        ("api/.git/HEAD", ""),
+        ("api/src/main.rs", ""),
+        ("api/debug.log", ""),
+        ("api/.gitignore", "*.log\n"),
+    ] {
+        fs::create_dir_all(base.join(path).parent().unwrap()).unwrap();
+        fs::write(base.join(path), contents).unwrap();
+    }
+    let search = |query: &str| {
+        let input = serde_json::json!({
+            "query": query,
+            "cwd": base.join("web"),
+            "additional_roots": ["../api"],
+        });
+        run_claude_search_raw(&input.to_string())
+    };
+
+    // Both roots are ranked together, paths relative to cwd
+    let output = search("main");
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines.contains(&"src/main.ts"), "{output}");
+    assert!(lines.contains(&"../api/src/main.rs"), "{output}");
+
+    // The additional root applies its own ignore rules
+    assert!(!search("debug").contains("debug.log"));
+
+    // root:name searches one root
+    let output = search("root:api main");
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        vec!["../api/src/main.rs"]
+    );
+    let output = search("root:web main");
+    assert_eq!(output.lines().collect::<Vec<_>>(), vec!["src/main.ts"]);
+    assert!(search("root:missing main").is_empty());
+
+    // Queries climbing out of cwd already cover the sibling
+    let output = search("../main");
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines.contains(&"../api/src/main.rs"), "{output}");
+    assert_eq!(lines.len(), 2, "{output}");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_unusual_file_names() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = create_test_project();
+    let base = dir.path();
+    fs::write(base.join("notes\nfinal.md"), "").unwrap();
+    fs::write(base.join(OsStr::from_bytes(b"notes-\xff.md")), "").unwrap();
+    let search = |format: &str| {
+        let input = serde_json::json!({ "query": "notes", "cwd": base, "format": format });
+        let mut child = Command::new(env!("CARGO_BIN_EXE_claude-search"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.as_mut().unwrap();
+        stdin.write_all(input.to_string().as_bytes()).unwrap();
+        child.wait_with_output().unwrap().stdout
+    };
+
+    // Lines stay one per result, quoted and escaped
+    let output = String::from_utf8(search("lines")).unwrap();
+    let mut lines: Vec<&str> = output.lines().collect();
+    lines.sort();
+    assert_eq!(lines, vec![r#""notes-\377.md""#, r#""notes\nfinal.md""#]);
+
+    // NUL-delimited output is exact
+    let output = search("nul");
+    let mut paths: Vec<&[u8]> = output
+        .split(|&b| b == 0)
+        .filter(|p| !p.is_empty())
+        .collect();
+    paths.sort();
+    assert_eq!(paths, vec![&b"notes\nfinal.md"[..], &b"notes-\xff.md"[..]]);
+
+    // JSON keeps the lossy path and adds the bytes
+    let output = String::from_utf8(search("json")).unwrap();
+    let raw = output
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|result| result.get("path_bytes").is_some())
+        .unwrap();
+    assert_eq!(raw["path"], "notes-\u{fffd}.md");
+    assert_eq!(raw["path_bytes"].as_array().unwrap()[6], 0xff);
+}
+
+#[test]
+fn test_import_paths() {
+    let dir = TempDir::new().unwrap();
+    let base = dir.path();
+    for (path, contents) in [
+        (".git/HEAD", ""),
+        ("Cargo.toml", "[package]\nname = \"claude-search\"\n"),
+        ("src/main.rs", ""),
+        ("src/walker.rs", ""),
+        ("tests/walker_walk_files.rs", ""),
+        (
+            "web/tsconfig.json",
+            r#"{ "compilerOptions": { "paths": { "@/*": ["src/*"] } } }"#,
+        ),
+        ("web/src/components/Button.tsx", ""),
+        ("web/src/components/ButtonGroup.tsx", ""),
+    ] {
+        fs::create_dir_all(base.join(path).parent().unwrap()).unwrap();
+        fs::write(base.join(path), contents).unwrap();
+    }
+    let search = |query: &str, cwd: &std::path::Path| {
+        let input = serde_json::json!({ "query": query, "cwd": cwd, "format": "json" });
+        run_claude_search_raw(&input.to_string())
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>()
+    };
+
+    let results = search("crate::walker::walk_files", base);
+    assert_eq!(results[0]["path"], "src/walker.rs");
+    assert_eq!(results[0]["import"], true);
+
+    let results = search("@/components/Button", &base.join("web"));
+    assert_eq!(results[0]["path"], "src/components/Button.tsx");
+    assert_eq!(results[0]["import"], true);
+    assert!(
+        results[1..]
+            .iter()
+            .all(|result| result.get("import").is_none())
+    );
+}
+
+#[test]
+fn test_pasted_paths() {
+    let dir = TempDir::new().unwrap();
+    let base = dir.path();
+    for path in [
+        ".git/HEAD",
+        "src/walker.rs",
+        "tests/walker.rs",
+        "lib/src/walker.rs",
+    ] {
+        fs::create_dir_all(base.join(path).parent().unwrap()).unwrap();
+        fs::write(base.join(path), "").unwrap();
+    }
+    let search = |query: &str| {
+        let input = serde_json::json!({ "query": query, "cwd": base });
+        run_claude_search_raw(&input.to_string())
+    };
+
+    // A path from a CI log: the files sharing its longest suffix, with the line
+    let output = search("/home/ci/build/repo/src/walker.rs:10:5");
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        vec!["src/walker.rs#L10", "lib/src/walker.rs#L10"]
+    );
+
+    // A forge URL, reduced to its path below the branch
+    let output = search("https://github.com/org/repo/blob/main/lib/src/walker.rs#L3-L8");
+    assert_eq!(output.lines().next(), Some("lib/src/walker.rs#L3-8"));
+
+    // Nothing local shares the file name
+    assert!(search("/home/ci/build/repo/src/other.rs:1").is_empty());
+
+    // One shared segment pins a file name, not a repository name
+    assert_eq!(search("/tmp/walker.rs").lines().count(), 3);
+    fs::write(base.join("repo"), "").unwrap();
+    assert!(search("https://github.com/org/repo").is_empty());
+}
+
+#[test]
+fn test_typo_suggestions() {
+    let dir = create_test_project();
+    let cwd = dir.path().to_str().unwrap();
+
+    // "carog" is not a subsequence of any path, but one swap from Cargo.toml
+    let output = run_claude_search("carog", cwd);
+    assert_eq!(output.lines().collect::<Vec<_>>(), vec!["Cargo.toml"]);
+
+    let input = serde_json::json!({ "query": "carog", "cwd": cwd, "format": "json" });
+    let output = run_claude_search_raw(&input.to_string());
+    let result: serde_json::Value = serde_json::from_str(output.trim()).unwrap();
+    assert_eq!(result["typo"], true);
+    assert_eq!(result["score"], 0);
+}